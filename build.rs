@@ -0,0 +1,30 @@
+use std::{env, fs, path::Path};
+
+// Generates a function mapping each day to the contents of `<dir>/<day>.txt`,
+// for whichever of those files are present.
+fn embed(dir: &str, function: &str) -> String {
+    println!("cargo:rerun-if-changed={dir}");
+    let dir = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join(dir);
+    let mut arms = String::new();
+    if let Ok(entries) = fs::read_dir(&dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "txt") {
+                let day = path.file_stem().unwrap().to_string_lossy();
+                arms += &format!("        {day:?} => Some(include_str!({path:?})),\n");
+            }
+        }
+    }
+    format!(
+        "fn {function}(day: &str) -> Option<&'static str> {{\n    match day {{\n{arms}        _ => None,\n    }}\n}}\n"
+    )
+}
+
+// Embeds whichever puzzle inputs and known answers are present so the binary
+// still has a fallback when no --input is given, without failing to compile
+// when some of them are missing.
+fn main() {
+    let source = embed("inputs", "embedded_input") + &embed("answers", "embedded_answers");
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("embedded.rs");
+    fs::write(out, source).unwrap();
+}
@@ -1,55 +1,66 @@
 use std::iter::from_fn;
 
-pub fn solve(input: &str) -> u32 {
-    input
-        .trim()
-        .lines()
-        .map(str::trim)
-        .map(|l| {
-            let mut digits = l.chars().filter(char::is_ascii_digit);
-            let first = digits.clone().next().unwrap();
-            let last = digits.next_back().unwrap();
-            format!("{first}{last}")
-        })
-        .map(|n| n.parse::<u32>().unwrap())
-        .sum()
+use crate::solution::{Answer, Error, Log, Result, Solution};
+
+pub struct Day1;
+
+impl Solution for Day1 {
+    const DAY: u32 = 1;
+    const TITLE: Option<&'static str> = Some("Trebuchet?!");
+
+    type Input<'a> = Vec<&'a str>;
+
+    fn parse(input: &str) -> Result<Self::Input<'_>> {
+        Ok(input.trim().lines().map(str::trim).collect())
+    }
+
+    fn part1(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        calibration_sum(input, |l| l.chars().filter_map(|c| c.to_digit(10)))
+    }
+
+    fn part2(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        calibration_sum(input, digits)
+    }
 }
 
-pub fn solve_2(input: &str) -> u32 {
-    fn digits(mut s: &str) -> impl Iterator<Item = u32> + '_ {
-        from_fn(move || {
-            while !s.is_empty() {
-                for (i, name) in [
-                    "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
-                ]
-                .into_iter()
-                .enumerate()
-                {
-                    if s.starts_with(name) {
-                        let (_, rest) = s.split_at(1);
-                        s = rest;
-                        return Some(i as u32 + 1);
-                    }
-                }
-                let (c, rest) = s.split_at(1);
-                s = rest;
-                if let Ok(digit) = c.parse() {
-                    return Some(digit);
+pub fn calibration_sum<'a, I>(lines: &[&'a str], digits: impl Fn(&'a str) -> I) -> Result<Answer>
+where
+    I: Iterator<Item = u32>,
+{
+    let mut total = 0;
+    for (i, &line) in lines.iter().enumerate() {
+        let mut digits = digits(line);
+        let Some(first) = digits.next() else {
+            return Err(Error::solve(format!("line {} has no digits", i + 1)));
+        };
+        total += first * 10 + digits.last().unwrap_or(first);
+    }
+    Ok(total.into())
+}
+
+pub fn digits(mut s: &str) -> impl Iterator<Item = u32> + '_ {
+    from_fn(move || {
+        while !s.is_empty() {
+            for (i, name) in [
+                "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                if s.starts_with(name) {
+                    let (_, rest) = s.split_at(1);
+                    s = rest;
+                    return Some(i as u32 + 1);
                 }
             }
-            None
-        })
-    }
-    input
-        .trim()
-        .lines()
-        .map(str::trim)
-        .map(|s| {
-            let first = digits(s).next().unwrap();
-            let last = digits(s).last().unwrap();
-            first * 10 + last
-        })
-        .sum()
+            let (c, rest) = s.split_at(1);
+            s = rest;
+            if let Ok(digit) = c.parse() {
+                return Some(digit);
+            }
+        }
+        None
+    })
 }
 
 #[cfg(test)]
@@ -59,12 +70,13 @@ mod test {
     #[test]
     fn test_sample() {
         assert_eq!(
-            solve(
+            Day1::solve(
                 "1abc2
                 pqr3stu8vwx
                 a1b2c3d4e5f
                 treb7uchet"
-            ),
+            )
+            .unwrap(),
             142
         )
     }
@@ -72,7 +84,7 @@ mod test {
     #[test]
     fn test_sample_2() {
         assert_eq!(
-            solve_2(
+            Day1::solve_2(
                 "two1nine
                 eightwothree
                 abcone2threexyz
@@ -80,13 +92,14 @@ mod test {
                 4nineeightseven2
                 zoneight234
                 7pqrstsixteen"
-            ),
+            )
+            .unwrap(),
             281
         )
     }
 
     #[test]
     fn test_simple_sample_2() {
-        assert_eq!(solve_2("onetwo"), 12)
+        assert_eq!(Day1::solve_2("onetwo").unwrap(), 12)
     }
 }
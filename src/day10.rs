@@ -1,72 +1,69 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 
+use glam::IVec2;
 use itertools::Itertools;
 
-fn parse(input: &str) -> ((i64, i64), HashMap<(i64, i64), Vec<(i64, i64)>>) {
-    let mut edges = HashMap::new();
-    let mut start = None;
-    for (y, line) in input.trim().lines().enumerate() {
-        for (x, c) in line.trim().chars().enumerate() {
-            let pos = (x as i64, y as i64);
-            let ends = match c {
-                '-' => [(-1, 0), (1, 0)],
-                '|' => [(0, -1), (0, 1)],
-                '7' => [(-1, 0), (0, 1)],
-                'F' => [(1, 0), (0, 1)],
-                'J' => [(-1, 0), (0, -1)],
-                'L' => [(1, 0), (0, -1)],
-                'S' => {
-                    start = Some(pos);
-                    continue;
-                }
-                '.' => continue,
-                _ => panic!(),
-            };
-            edges.insert(
-                pos,
-                ends.into_iter()
-                    .map(|(dx, dy)| (x as i64 + dx, y as i64 + dy))
-                    .collect(),
-            );
-        }
-    }
-    (start.unwrap(), edges)
+use crate::{
+    solution::{Answer, Error, Log, Result, Solution},
+    utils::{Grid, ORTHOGONAL},
+};
+
+pub fn parse(input: &str) -> Result<(IVec2, Grid<char>)> {
+    let grid = Grid::parse(input, |c| "-|7FJLS.".contains(c).then_some(c))?;
+    let start = grid
+        .find(|&c| c == 'S')
+        .ok_or_else(|| Error::solve("no start tile"))?;
+    Ok((start, grid))
+}
+
+/// The tiles that the pipe at `p` connects to.
+fn ends(grid: &Grid<char>, p: IVec2) -> [IVec2; 2] {
+    let (a, b) = match grid.get(p) {
+        Some('-') => ((-1, 0), (1, 0)),
+        Some('|') => ((0, -1), (0, 1)),
+        Some('7') => ((-1, 0), (0, 1)),
+        Some('F') => ((1, 0), (0, 1)),
+        Some('J') => ((-1, 0), (0, -1)),
+        Some('L') => ((1, 0), (0, -1)),
+        _ => ((0, 0), (0, 0)),
+    };
+    [p + IVec2::from(a), p + IVec2::from(b)]
 }
 
-fn main_loop(start: (i64, i64), edges: HashMap<(i64, i64), Vec<(i64, i64)>>) -> Vec<(i64, i64)> {
-    let &first = edges.iter().find(|(_, v)| v.contains(&start)).unwrap().0;
+pub fn main_loop(start: IVec2, grid: &Grid<char>) -> Result<Vec<IVec2>> {
+    let broken = || Error::solve("the main loop is broken");
+    let first = grid
+        .neighbours4(start)
+        .find(|&p| ends(grid, p).contains(&start))
+        .ok_or_else(broken)?;
     let mut path = vec![start];
     let (mut prev, mut pos) = (start, first);
     while pos != start {
         path.push(pos);
-        for &next in edges.get(&pos).unwrap() {
-            if next != prev {
-                (prev, pos) = (pos, next);
-                break;
-            }
-        }
+        let [a, b] = ends(grid, pos);
+        let next = if prev == a {
+            b
+        } else if prev == b {
+            a
+        } else {
+            return Err(broken());
+        };
+        (prev, pos) = (pos, next);
     }
-    path
+    Ok(path)
 }
 
-pub fn solve(input: &str) -> usize {
-    let (start, edges) = parse(input);
-    main_loop(start, edges).len() / 2
+fn turn_left(prev: IVec2, pos: IVec2) -> IVec2 {
+    let d = pos - prev;
+    pos + IVec2::new(d.y, -d.x)
 }
 
-fn turn_left(prev: (i64, i64), pos: (i64, i64)) -> (i64, i64) {
-    let (dx, dy) = (pos.0 - prev.0, pos.1 - prev.1);
-    (pos.0 + dy, pos.1 - dx)
+fn turn_right(prev: IVec2, pos: IVec2) -> IVec2 {
+    let d = pos - prev;
+    pos + IVec2::new(-d.y, d.x)
 }
 
-fn turn_right(prev: (i64, i64), pos: (i64, i64)) -> (i64, i64) {
-    let (dx, dy) = (pos.0 - prev.0, pos.1 - prev.1);
-    (pos.0 - dy, pos.1 + dx)
-}
-
-pub fn solve_2(input: &str) -> usize {
-    let (start, edges) = parse(input);
-    let path = main_loop(start, edges);
+pub fn enclosed_area(path: Vec<IVec2>) -> usize {
     let (mut left_turns, mut right_turns) = (0, 0);
     let (mut left_seeds, mut right_seeds) = (Vec::new(), Vec::new());
     // Find all the spaces to the left and right of the path
@@ -94,20 +91,39 @@ pub fn solve_2(input: &str) -> usize {
     // Flood fill from the corresponding seeds, don't count any pipes
     let mut seen: HashSet<_> = path.into_iter().collect();
     let mut enclosed = 0;
-    while let Some((x, y)) = stack.pop() {
-        if !seen.insert((x, y)) {
+    while let Some(p) = stack.pop() {
+        if !seen.insert(p) {
             continue;
         }
         enclosed += 1;
-        stack.extend(
-            [(1, 0), (-1, 0), (0, 1), (0, -1)]
-                .into_iter()
-                .map(|(dx, dy)| (x + dx, y + dy)),
-        );
+        stack.extend(ORTHOGONAL.map(|d| p + d));
     }
     enclosed
 }
 
+pub struct Day10;
+
+impl Solution for Day10 {
+    const DAY: u32 = 10;
+    const TITLE: Option<&'static str> = Some("Pipe Maze");
+
+    type Input<'a> = (IVec2, Grid<char>);
+
+    fn parse(input: &str) -> Result<Self::Input<'_>> {
+        parse(input)
+    }
+
+    fn part1(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        let &(start, ref grid) = input;
+        Ok((main_loop(start, grid)?.len() / 2).into())
+    }
+
+    fn part2(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        let &(start, ref grid) = input;
+        Ok(enclosed_area(main_loop(start, grid)?).into())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -120,7 +136,7 @@ mod test {
         SJ.L7
         |F--J
         LJ...";
-        assert_eq!(solve(SAMPLE), 8);
+        assert_eq!(Day10::solve(SAMPLE).unwrap(), 8);
     }
 
     #[test]
@@ -131,7 +147,7 @@ mod test {
         .|.|.
         .L-J.
         .....";
-        assert_eq!(solve(SAMPLE), 4);
+        assert_eq!(Day10::solve(SAMPLE).unwrap(), 4);
     }
 
     #[test]
@@ -146,7 +162,7 @@ mod test {
         .|..|.|..|.
         .L--J.L--J.
         ...........";
-        assert_eq!(solve_2(SAMPLE), 4);
+        assert_eq!(Day10::solve_2(SAMPLE).unwrap(), 4);
     }
 
     #[test]
@@ -162,7 +178,7 @@ mod test {
         7-L-JL7||F7|L7F-7F7|
         L.L7LFJ|||||FJL7||LJ
         L7JLJL-JLJLJL--JLJ.L";
-        assert_eq!(solve_2(SAMPLE), 10);
+        assert_eq!(Day10::solve_2(SAMPLE).unwrap(), 10);
     }
 
     #[test]
@@ -178,6 +194,6 @@ mod test {
         .....|FJLJ|FJ|F7|.LJ
         ....FJL-7.||.||||...
         ....L---J.LJ.LJLJ...";
-        assert_eq!(solve_2(SAMPLE), 8);
+        assert_eq!(Day10::solve_2(SAMPLE).unwrap(), 8);
     }
 }
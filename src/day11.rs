@@ -1,59 +1,62 @@
-use std::collections::HashSet;
-
 use itertools::Itertools;
 
-fn parse(input: &str) -> HashSet<(usize, usize)> {
-    let mut result = HashSet::new();
-    for (y, line) in input.trim().lines().enumerate() {
-        for (x, c) in line.trim().chars().enumerate() {
-            match c {
-                '#' => {
-                    result.insert((x, y));
-                }
-                '.' => (),
-                _ => panic!(),
-            }
-        }
-    }
-    result
+use crate::{
+    solution::{Answer, Log, Result, Solution},
+    utils::Grid,
+};
+
+pub fn parse(input: &str) -> Result<Grid<bool>> {
+    Grid::parse(input, |c| match c {
+        '#' => Some(true),
+        '.' => Some(false),
+        _ => None,
+    })
 }
 
-fn expand_dim<'a>(
-    factor: usize,
-    galaxies: impl IntoIterator<Item = &'a (usize, usize)>,
-    get_dim: impl Fn((usize, usize)) -> usize,
-) -> Vec<usize> {
-    let occupied: HashSet<_> = galaxies.into_iter().copied().map(get_dim).collect();
-    let &max = occupied.iter().max().unwrap();
-    let mut result = Vec::new();
-    for p in 0..=max {
-        let &last = result.last().unwrap_or(&0);
-        result.push(last + if occupied.contains(&p) { 1 } else { factor });
-    }
-    result
+/// Where each row or column ends up once the empty ones are `factor` wide.
+pub fn expand_dim(factor: usize, empty: impl Iterator<Item = bool>) -> Vec<usize> {
+    empty
+        .scan(0, |pos, empty| {
+            *pos += if empty { factor } else { 1 };
+            Some(*pos)
+        })
+        .collect()
 }
 
-fn run(input: &str, factor: usize) -> usize {
-    let galaxies = parse(input);
-    let real_cols = expand_dim(factor, &galaxies, |(x, _)| x);
-    let real_rows = expand_dim(factor, &galaxies, |(_, y)| y);
-    galaxies
+pub fn run(galaxies: &Grid<bool>, factor: usize) -> usize {
+    let real_cols = expand_dim(factor, galaxies.columns().map(|mut c| !c.any(|&g| g)));
+    let real_rows = expand_dim(factor, galaxies.rows().map(|r| !r.contains(&true)));
+    let positions: Vec<_> = galaxies
         .iter()
+        .filter(|(_, &g)| g)
+        .map(|(p, _)| (real_cols[p.x as usize], real_rows[p.y as usize]))
+        .collect();
+    positions
+        .into_iter()
         .tuple_combinations()
-        .map(|(&a, &b)| {
-            let a = (real_cols[a.0], real_rows[a.1]);
-            let b = (real_cols[b.0], real_rows[b.1]);
-            a.0.abs_diff(b.0) + a.1.abs_diff(b.1)
-        })
+        .map(|(a, b)| a.0.abs_diff(b.0) + a.1.abs_diff(b.1))
         .sum()
 }
 
-pub fn solve(input: &str) -> usize {
-    run(input, 2)
-}
+pub struct Day11;
 
-pub fn solve_2(input: &str) -> usize {
-    run(input, 1_000_000)
+impl Solution for Day11 {
+    const DAY: u32 = 11;
+    const TITLE: Option<&'static str> = Some("Cosmic Expansion");
+
+    type Input<'a> = Grid<bool>;
+
+    fn parse(input: &str) -> Result<Self::Input<'_>> {
+        parse(input)
+    }
+
+    fn part1(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        Ok(run(input, 2).into())
+    }
+
+    fn part2(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        Ok(run(input, 1_000_000).into())
+    }
 }
 
 #[cfg(test)]
@@ -74,12 +77,13 @@ mod test {
 
     #[test]
     fn test_sample() {
-        assert_eq!(solve(SAMPLE), 374);
+        assert_eq!(Day11::solve(SAMPLE).unwrap(), 374);
     }
 
     #[test]
     fn test_sample_2() {
-        assert_eq!(run(SAMPLE, 10), 1030);
-        assert_eq!(run(SAMPLE, 100), 8410);
+        let galaxies = parse(SAMPLE).unwrap();
+        assert_eq!(run(&galaxies, 10), 1030);
+        assert_eq!(run(&galaxies, 100), 8410);
     }
 }
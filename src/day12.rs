@@ -9,14 +9,20 @@ use nom::{
     IResult,
 };
 
-struct Problem {
+use crate::solution::{parse_all, Answer, Log, Result, Solution};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+#[derive(Clone)]
+pub struct Problem {
     memo: HashMap<(usize, usize, usize), usize>,
     springs: Vec<char>,
     groups: Vec<usize>,
 }
 
 impl Problem {
-    fn new(input: &str) -> IResult<&str, Self> {
+    pub fn new(input: &str) -> IResult<&str, Self> {
         map(
             pair(
                 many1(one_of("#.?")),
@@ -33,7 +39,7 @@ impl Problem {
         )(input)
     }
 
-    fn unfold(mut self) -> Self {
+    pub fn unfold(mut self) -> Self {
         let (springs, groups) = (self.springs.clone(), self.groups.clone());
         for _ in 1..5 {
             self.springs.push('?');
@@ -43,7 +49,7 @@ impl Problem {
         self
     }
 
-    fn solve(&mut self, group_i: usize, current_len: usize, spring_i: usize) -> usize {
+    pub fn solve(&mut self, group_i: usize, current_len: usize, spring_i: usize) -> usize {
         // Some yucky dynamic programming. Couldn't think of a better way to
         // handle the base case.
         if spring_i == self.springs.len()
@@ -73,27 +79,53 @@ impl Problem {
             '#' => broken,
             '.' => ok,
             '?' => broken + ok,
-            _ => panic!(),
+            _ => unreachable!(),
         };
         self.memo.insert((group_i, current_len, spring_i), answer);
         answer
     }
 }
 
-fn parse(input: &str) -> Vec<Problem> {
-    many1(preceded(multispace0, Problem::new))(input).unwrap().1
+pub fn parse(input: &str) -> Result<Vec<Problem>> {
+    parse_all(input, many1(preceded(multispace0, Problem::new)))
 }
 
-pub fn solve(input: &str) -> usize {
-    parse(input).into_iter().map(|mut p| p.solve(0, 0, 0)).sum()
-}
+pub struct Day12;
+
+impl Solution for Day12 {
+    const DAY: u32 = 12;
+    const TITLE: Option<&'static str> = Some("Hot Springs");
+
+    type Input<'a> = Vec<Problem>;
 
-pub fn solve_2(input: &str) -> usize {
-    parse(input)
-        .into_iter()
-        .map(Problem::unfold)
-        .map(|mut p| p.solve(0, 0, 0))
-        .sum()
+    fn parse(input: &str) -> Result<Self::Input<'_>> {
+        parse(input)
+    }
+
+    fn part1(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        #[cfg(feature = "parallel")]
+        let problems = input.par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let problems = input.iter();
+        Ok(problems
+            .cloned()
+            .map(|mut p| p.solve(0, 0, 0))
+            .sum::<usize>()
+            .into())
+    }
+
+    fn part2(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        #[cfg(feature = "parallel")]
+        let problems = input.par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let problems = input.iter();
+        Ok(problems
+            .cloned()
+            .map(Problem::unfold)
+            .map(|mut p| p.solve(0, 0, 0))
+            .sum::<usize>()
+            .into())
+    }
 }
 
 #[cfg(test)]
@@ -110,11 +142,11 @@ mod test {
 
     #[test]
     fn test_sample() {
-        assert_eq!(solve(SAMPLE), 21);
+        assert_eq!(Day12::solve(SAMPLE).unwrap(), 21);
     }
 
     #[test]
     fn test_sample_2() {
-        assert_eq!(solve_2(SAMPLE), 525152);
+        assert_eq!(Day12::solve_2(SAMPLE).unwrap(), 525152);
     }
 }
@@ -1,81 +1,92 @@
 use std::iter::zip;
 
-use itertools::Itertools;
+use crate::{
+    solution::{Answer, Error, Log, Result, Solution},
+    utils::Grid,
+};
 
-fn parse(input: &str) -> Vec<Vec<Vec<char>>> {
-    let mut patterns = Vec::new();
-    let mut pattern = Vec::new();
-    for line in input.trim().lines() {
-        let row: Vec<_> = line.trim().chars().collect();
-        if row.is_empty() {
-            if !pattern.is_empty() {
-                patterns.push(pattern);
-                pattern = Vec::new();
-            }
-        } else {
-            pattern.push(row);
+pub fn parse(input: &str) -> Result<Vec<Grid<bool>>> {
+    let mut blocks = vec![Vec::new()];
+    for line in input.trim().lines().map(str::trim) {
+        match blocks.last_mut() {
+            Some(block) if !line.is_empty() => block.push(line),
+            Some(block) if !block.is_empty() => blocks.push(Vec::new()),
+            _ => (),
         }
     }
-    if !pattern.is_empty() {
-        patterns.push(pattern);
-    }
-    patterns
+    blocks
+        .into_iter()
+        .filter(|block| !block.is_empty())
+        .map(|block| {
+            Grid::parse_lines(input, block, |c| match c {
+                '#' => Some(true),
+                '.' => Some(false),
+                _ => None,
+            })
+        })
+        .collect()
 }
 
-fn find_symmetry(pattern: &Vec<Vec<char>>) -> Vec<usize> {
-    let mut symmetries = Vec::new();
-    'next_symmetry: for y in 1..pattern.len() {
-        for (a, b) in zip(pattern[..y].iter().rev(), &pattern[y..]) {
-            if a != b {
-                continue 'next_symmetry;
-            }
-        }
-        symmetries.push(100 * y);
-    }
-    'next_symmetry: for x in 1..pattern[0].len() {
-        for row in pattern {
-            for (a, b) in zip(row[..x].iter().rev(), &row[x..]) {
-                if a != b {
-                    continue 'next_symmetry;
-                }
-            }
-        }
-        symmetries.push(x);
-    }
-    symmetries
+/// The positions of the mirrors between lines that reflect all the others.
+fn reflections<T: PartialEq>(lines: &[T]) -> impl Iterator<Item = usize> + '_ {
+    (1..lines.len()).filter(|&i| zip(lines[..i].iter().rev(), &lines[i..]).all(|(a, b)| a == b))
 }
 
-pub fn solve(input: &str) -> usize {
-    parse(input)
-        .iter()
-        .map(|pattern| find_symmetry(pattern)[0])
-        .sum()
+pub fn find_symmetry(pattern: &Grid<bool>) -> Vec<usize> {
+    let rows: Vec<_> = pattern.rows().collect();
+    let columns: Vec<Vec<_>> = pattern.columns().map(|c| c.collect()).collect();
+    reflections(&rows)
+        .map(|y| 100 * y)
+        .chain(reflections(&columns))
+        .collect()
 }
 
-pub fn solve_2(input: &str) -> usize {
-    parse(input)
-        .into_iter()
-        .map(|mut pattern| {
-            let old_symmetry = find_symmetry(&pattern)[0];
-            for (y, x) in (0..pattern.len()).cartesian_product(0..pattern[0].len()) {
-                let c = pattern[y][x];
-                match c {
-                    '.' => pattern[y][x] = '#',
-                    '#' => pattern[y][x] = '.',
-                    _ => panic!(),
-                }
-                if let Some(symmetry) = find_symmetry(&pattern)
-                    .into_iter()
-                    .filter(|&s| s != old_symmetry)
-                    .next()
-                {
-                    return symmetry;
+pub struct Day13;
+
+impl Solution for Day13 {
+    const DAY: u32 = 13;
+    const TITLE: Option<&'static str> = Some("Point of Incidence");
+
+    type Input<'a> = Vec<Grid<bool>>;
+
+    fn parse(input: &str) -> Result<Self::Input<'_>> {
+        parse(input)
+    }
+
+    fn part1(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        Ok(input
+            .iter()
+            .map(|pattern| {
+                find_symmetry(pattern)
+                    .first()
+                    .copied()
+                    .ok_or_else(|| Error::solve("no reflection found"))
+            })
+            .sum::<Result<usize>>()?
+            .into())
+    }
+
+    fn part2(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        Ok(input
+            .iter()
+            .cloned()
+            .map(|mut pattern| {
+                let old_symmetry = find_symmetry(&pattern).first().copied();
+                for p in pattern.positions() {
+                    pattern[p] = !pattern[p];
+                    if let Some(symmetry) = find_symmetry(&pattern)
+                        .into_iter()
+                        .find(|&s| Some(s) != old_symmetry)
+                    {
+                        return Ok(symmetry);
+                    }
+                    pattern[p] = !pattern[p];
                 }
-                pattern[y][x] = c;
-            }
-            panic!()
-        })
-        .sum()
+                Err(Error::solve("no smudged reflection found"))
+            })
+            .sum::<Result<usize>>()?
+            .into())
+    }
 }
 
 #[cfg(test)]
@@ -101,11 +112,11 @@ mod test {
 
     #[test]
     fn test_sample() {
-        assert_eq!(solve(SAMPLE), 405);
+        assert_eq!(Day13::solve(SAMPLE).unwrap(), 405);
     }
 
     #[test]
     fn test_sample_2() {
-        assert_eq!(solve_2(SAMPLE), 400);
+        assert_eq!(Day13::solve_2(SAMPLE).unwrap(), 400);
     }
 }
@@ -1,85 +1,74 @@
 use std::collections::HashMap;
 
-use itertools::Itertools;
+use glam::IVec2;
+
+use crate::{
+    solution::{Answer, Log, Result, Solution},
+    utils::Grid,
+};
 
 #[derive(PartialEq, Eq, Hash, Clone, Copy)]
-enum Rock {
+pub enum Rock {
     Empty,
     Ball,
     Cube,
 }
 
 #[derive(PartialEq, Eq, Hash, Clone)]
-struct Platform {
-    origin: (isize, isize),
-    rocks: Vec<Vec<Rock>>,
-    x_basis: (isize, isize),
-    y_basis: (isize, isize),
+pub struct Platform {
+    rocks: Grid<Rock>,
+    /// How many quarter turns clockwise from the input's orientation.
+    turns: usize,
 }
 
 impl Platform {
-    fn new(input: &str) -> Self {
-        let rocks: Vec<Vec<Rock>> = input
-            .trim()
-            .lines()
-            .map(|line| {
-                line.trim()
-                    .chars()
-                    .map(|c| match c {
-                        '.' => Rock::Empty,
-                        'O' => Rock::Ball,
-                        '#' => Rock::Cube,
-                        _ => panic!(),
-                    })
-                    .collect()
-            })
-            .collect();
-        Self {
-            rocks,
-            origin: (0, 0),
-            x_basis: (1, 0),
-            y_basis: (0, 1),
-        }
+    pub fn new(input: &str) -> Result<Self> {
+        let rocks = Grid::parse(input, |c| match c {
+            '.' => Some(Rock::Empty),
+            'O' => Some(Rock::Ball),
+            '#' => Some(Rock::Cube),
+            _ => None,
+        })?;
+        Ok(Self { rocks, turns: 0 })
     }
 
-    fn tilt_next(&mut self) {
-        let max_x = self.rocks[0].len() as isize - 1;
-        let max_y = self.rocks.len() as isize - 1;
-        let (mut x0, mut y0) = (self.origin.0 * max_x, self.origin.1 * max_y);
-        while (0..=max_x).contains(&x0) && (0..=max_y).contains(&y0) {
-            let (mut x, mut y) = (x0, y0);
-            let (mut xi, mut yi) = (x0, y0);
-            while (0..=max_x).contains(&x) && (0..=max_y).contains(&y) {
-                match self.rocks[y as usize][x as usize] {
+    /// Tilts north, then turns the platform clockwise so that the next tilt
+    /// goes the next way around the spin cycle.
+    pub fn tilt_next(&mut self) {
+        for x in 0..self.rocks.width() as i32 {
+            let mut free = 0;
+            for y in 0..self.rocks.height() as i32 {
+                let p = IVec2::new(x, y);
+                match self.rocks[p] {
                     Rock::Empty => (),
-                    Rock::Cube => (xi, yi) = (x + self.y_basis.0, y + self.y_basis.1),
+                    Rock::Cube => free = y + 1,
                     Rock::Ball => {
-                        self.rocks[y as usize][x as usize] = Rock::Empty;
-                        self.rocks[yi as usize][xi as usize] = Rock::Ball;
-                        (xi, yi) = (xi + self.y_basis.0, yi + self.y_basis.1);
+                        self.rocks[p] = Rock::Empty;
+                        self.rocks[IVec2::new(x, free)] = Rock::Ball;
+                        free += 1;
                     }
                 }
-                (x, y) = (x + self.y_basis.0, y + self.y_basis.1);
             }
-            (x0, y0) = (x0 + self.x_basis.0, y0 + self.x_basis.1);
         }
-        self.x_basis = (self.x_basis.1, -self.x_basis.0);
-        self.y_basis = (self.y_basis.1, -self.y_basis.0);
-        self.origin = (self.origin.1, 1 - self.origin.0);
+        self.rocks = self.rocks.rotate_cw();
+        self.turns = (self.turns + 1) % 4;
     }
 
-    fn total_load(&self) -> usize {
-        let width = self.rocks[0].len();
-        let height = self.rocks.len();
-        (0..height)
-            .cartesian_product(0..width)
-            .filter(|&(y, x)| self.rocks[y][x] == Rock::Ball)
-            .map(|(y, _)| height - y)
+    /// The load on the north support beams of the original orientation.
+    pub fn total_load(&self) -> usize {
+        let mut rocks = self.rocks.clone();
+        for _ in 0..self.turns {
+            rocks = rocks.rotate_ccw();
+        }
+        rocks
+            .iter()
+            .filter(|&(_, &rock)| rock == Rock::Ball)
+            .map(|(p, _)| rocks.height() - p.y as usize)
             .sum()
     }
 }
 
-fn run(mut platform: Platform, iters: usize) -> Platform {
+pub fn run(mut platform: Platform, iters: usize) -> Platform {
     let mut seen: HashMap<Platform, usize> = HashMap::new();
     for i in 0..iters {
         if let Some(prev_i) = seen.insert(platform.clone(), i) {
@@ -90,12 +79,25 @@ fn run(mut platform: Platform, iters: usize) -> Platform {
     platform
 }
 
-pub fn solve(input: &str) -> usize {
-    run(Platform::new(input), 1).total_load()
-}
+pub struct Day14;
+
+impl Solution for Day14 {
+    const DAY: u32 = 14;
+    const TITLE: Option<&'static str> = Some("Parabolic Reflector Dish");
+
+    type Input<'a> = Platform;
 
-pub fn solve_2(input: &str) -> usize {
-    run(Platform::new(input), 4 * 1_000_000_000).total_load()
+    fn parse(input: &str) -> Result<Self::Input<'_>> {
+        Platform::new(input)
+    }
+
+    fn part1(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        Ok(run(input.clone(), 1).total_load().into())
+    }
+
+    fn part2(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        Ok(run(input.clone(), 4 * 1_000_000_000).total_load().into())
+    }
 }
 
 #[cfg(test)]
@@ -116,11 +118,11 @@ mod test {
 
     #[test]
     fn test_sample() {
-        assert_eq!(solve(SAMPLE), 136)
+        assert_eq!(Day14::solve(SAMPLE).unwrap(), 136)
     }
 
     #[test]
     fn test_rotation() {
-        assert_eq!(solve_2(SAMPLE), 64)
+        assert_eq!(Day14::solve_2(SAMPLE).unwrap(), 64)
     }
 }
@@ -11,7 +11,9 @@ use nom::{
     IResult,
 };
 
-enum Instr<'a> {
+use crate::solution::{parse_all, Answer, Log, Result, Solution};
+
+pub enum Instr<'a> {
     Add(&'a str, usize),
     Remove(&'a str),
 }
@@ -25,59 +27,78 @@ impl Display for Instr<'_> {
     }
 }
 
-fn parse(input: &str) -> Vec<Instr> {
+pub fn parse(input: &str) -> Result<Vec<Instr<'_>>> {
     fn number(input: &str) -> IResult<&str, usize> {
         map_res(digit1, str::parse)(input)
     }
-    separated_list1(
-        tag(","),
-        preceded(
-            multispace0,
-            alt((
-                map(terminated(alpha1, tag("-")), Instr::Remove),
-                map(pair(alpha1, preceded(tag("="), number)), |(l, n)| {
-                    Instr::Add(l, n)
-                }),
-            )),
+    parse_all(
+        input,
+        separated_list1(
+            tag(","),
+            preceded(
+                multispace0,
+                alt((
+                    map(terminated(alpha1, tag("-")), Instr::Remove),
+                    map(pair(alpha1, preceded(tag("="), number)), |(l, n)| {
+                        Instr::Add(l, n)
+                    }),
+                )),
+            ),
         ),
-    )(input)
-    .unwrap()
-    .1
+    )
 }
 
-fn hash(s: impl AsRef<str>) -> usize {
+pub fn hash(s: impl AsRef<str>) -> usize {
     s.as_ref()
         .chars()
         .map(|c| c as usize)
         .fold(0, |acc, c| ((acc + c) * 17) % 256)
 }
 
-pub fn solve(input: &str) -> usize {
-    parse(input).iter().map(Instr::to_string).map(hash).sum()
-}
+pub struct Day15;
 
-pub fn solve_2(input: &str) -> usize {
-    let mut boxes = vec![HashMap::<&str, (usize, usize)>::new(); 256];
-    for (i, instr) in parse(input).into_iter().enumerate() {
-        match instr {
-            Instr::Add(label, fl) => {
-                boxes[hash(label)]
-                    .entry(label)
-                    .and_modify(|(_, old_fl)| *old_fl = fl)
-                    .or_insert((i, fl));
-            }
-            Instr::Remove(label) => {
-                boxes[hash(label)].remove(label);
+impl Solution for Day15 {
+    const DAY: u32 = 15;
+    const TITLE: Option<&'static str> = Some("Lens Library");
+
+    type Input<'a> = Vec<Instr<'a>>;
+
+    fn parse(input: &str) -> Result<Self::Input<'_>> {
+        parse(input)
+    }
+
+    fn part1(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        Ok(input
+            .iter()
+            .map(Instr::to_string)
+            .map(hash)
+            .sum::<usize>()
+            .into())
+    }
+
+    fn part2(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        let mut boxes = vec![HashMap::<&str, (usize, usize)>::new(); 256];
+        for (i, instr) in input.iter().enumerate() {
+            match *instr {
+                Instr::Add(label, fl) => {
+                    boxes[hash(label)]
+                        .entry(label)
+                        .and_modify(|(_, old_fl)| *old_fl = fl)
+                        .or_insert((i, fl));
+                }
+                Instr::Remove(label) => {
+                    boxes[hash(label)].remove(label);
+                }
             }
         }
-    }
-    let mut result = 0;
-    for (box_n, hm) in boxes.into_iter().enumerate() {
-        for (i, (_, fl)) in hm.into_values().sorted().enumerate() {
-            result += (box_n + 1) * (i + 1) * fl;
+        let mut result = 0;
+        for (box_n, hm) in boxes.into_iter().enumerate() {
+            for (i, (_, fl)) in hm.into_values().sorted().enumerate() {
+                result += (box_n + 1) * (i + 1) * fl;
+            }
         }
+        Ok(result.into())
     }
-    result
 }
 
 #[cfg(test)]
@@ -88,11 +109,11 @@ mod test {
 
     #[test]
     fn test_sample() {
-        assert_eq!(solve(SAMPLE), 1320);
+        assert_eq!(Day15::solve(SAMPLE).unwrap(), 1320);
     }
 
     #[test]
     fn test_sample_2() {
-        assert_eq!(solve_2(SAMPLE), 145);
+        assert_eq!(Day15::solve_2(SAMPLE).unwrap(), 145);
     }
 }
@@ -1,54 +1,79 @@
-use std::collections::{HashMap, HashSet};
+use glam::{IVec2, Vec2Swizzles};
 
-fn parse(input: &str) -> Vec<Vec<char>> {
-    input
-        .trim()
-        .lines()
-        .map(|line| line.trim().chars().collect())
-        .collect()
+use crate::{
+    solution::{Answer, Error, Log, Result, Solution},
+    utils::{Grid, ORTHOGONAL},
+};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+pub fn parse(input: &str) -> Result<Grid<char>> {
+    Grid::parse(input, |c| r"\/-|.".contains(c).then_some(c))
 }
 
-fn run(grid: &[Vec<char>], start: ((i32, i32), (i32, i32))) -> usize {
-    let height = grid.len() as i32;
-    let width = grid[0].len() as i32;
+/// Counts the tiles energised by a beam entering at `start.0` going in the
+/// orthogonal direction `start.1`.
+pub fn run(grid: &Grid<char>, start: (IVec2, IVec2)) -> Result<usize> {
+    if !ORTHOGONAL.contains(&start.1) {
+        return Err(Error::solve(format!(
+            "the beam must start going orthogonally, not {}",
+            start.1
+        )));
+    }
+    // The directions that beams have already passed through each tile in.
+    let mut seen = grid.map(|_| 0u8);
     let mut stack = vec![start];
-    let mut seen = HashMap::<_, HashSet<_>>::new();
-    while let Some(((x, y), (dx, dy))) = stack.pop() {
-        if !(0..height).contains(&y) || !(0..width).contains(&x) {
-            continue;
-        }
-        if !seen.entry((x, y)).or_default().insert((dx, dy)) {
-            continue;
+    while let Some((p, d)) = stack.pop() {
+        let bit = 1 << ORTHOGONAL.iter().position(|&o| o == d).unwrap();
+        match seen.get_mut(p) {
+            Some(dirs) if *dirs & bit == 0 => *dirs |= bit,
+            _ => continue,
         }
-        let dirs = match grid[y as usize][x as usize] {
-            '\\' => vec![(dy, dx)],
-            '/' => vec![(-dy, -dx)],
-            '-' if dx == 0 => vec![(-1, 0), (1, 0)],
-            '|' if dy == 0 => vec![(0, -1), (0, 1)],
-            '.' | '-' | '|' => vec![(dx, dy)],
-            _ => panic!(),
+        let dirs = match grid[p] {
+            '\\' => vec![d.yx()],
+            '/' => vec![-d.yx()],
+            '-' if d.x == 0 => vec![IVec2::NEG_X, IVec2::X],
+            '|' if d.y == 0 => vec![IVec2::NEG_Y, IVec2::Y],
+            _ => vec![d],
         };
-        for (dx, dy) in dirs {
-            stack.push(((x + dx, y + dy), (dx, dy)));
-        }
+        stack.extend(dirs.into_iter().map(|d| (p + d, d)));
     }
-    seen.len()
+    Ok(seen.values().filter(|&&dirs| dirs != 0).count())
 }
 
-pub fn solve(input: &str) -> usize {
-    run(&parse(input), ((0, 0), (1, 0)))
-}
+pub struct Day16;
+
+impl Solution for Day16 {
+    const DAY: u32 = 16;
+    const TITLE: Option<&'static str> = Some("The Floor Will Be Lava");
+
+    type Input<'a> = Grid<char>;
 
-pub fn solve_2(input: &str) -> usize {
-    let grid = parse(input);
-    let height = grid.len() as i32;
-    let width = grid[0].len() as i32;
-    (0..height)
-        .flat_map(|y| [((0, y), (1, 0)), ((width - 1, y), (-1, 0))])
-        .chain((0..width).flat_map(|x| [((x, 0), (0, 1)), ((x, height - 1), (0, -1))]))
-        .map(|start| run(&grid, start))
-        .max()
-        .unwrap()
+    fn parse(input: &str) -> Result<Self::Input<'_>> {
+        parse(input)
+    }
+
+    fn part1(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        Ok(run(input, (IVec2::ZERO, IVec2::X))?.into())
+    }
+
+    fn part2(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        let (width, height) = (input.width() as i32, input.height() as i32);
+        let starts: Vec<_> = (0..height)
+            .flat_map(|y| [((0, y), (1, 0)), ((width - 1, y), (-1, 0))])
+            .chain((0..width).flat_map(|x| [((x, 0), (0, 1)), ((x, height - 1), (0, -1))]))
+            .map(|(p, d)| (p.into(), d.into()))
+            .collect();
+        #[cfg(feature = "parallel")]
+        let starts = starts.into_par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let starts = starts.into_iter();
+        let counts: Vec<_> = starts
+            .map(|start| run(input, start))
+            .collect::<Result<_>>()?;
+        Ok(counts.into_iter().max().unwrap().into())
+    }
 }
 
 #[cfg(test)]
@@ -69,11 +94,17 @@ mod test {
 
     #[test]
     fn test_sample() {
-        assert_eq!(solve(SAMPLE), 46);
+        assert_eq!(Day16::solve(SAMPLE).unwrap(), 46);
     }
 
     #[test]
     fn test_sample_2() {
-        assert_eq!(solve_2(SAMPLE), 51);
+        assert_eq!(Day16::solve_2(SAMPLE).unwrap(), 51);
+    }
+
+    #[test]
+    fn test_diagonal_start() {
+        let grid = parse(SAMPLE).unwrap();
+        assert!(run(&grid, (IVec2::ZERO, IVec2::ONE)).is_err());
     }
 }
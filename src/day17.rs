@@ -1,55 +1,200 @@
-use std::collections::{BTreeSet, HashSet};
-
-fn parse(input: &str) -> Vec<Vec<usize>> {
-    input
-        .trim()
-        .lines()
-        .map(|line| {
-            line.trim()
-                .chars()
-                .map(|c| c.to_digit(10).unwrap() as usize)
-                .collect()
-        })
-        .collect()
+use glam::IVec2;
+
+use crate::{
+    solution::{Answer, Error, Log, Result, Solution},
+    utils::{search::astar, Grid, ALL_DIRECTIONS, ORTHOGONAL},
+};
+
+pub fn parse(input: &str) -> Result<Grid<usize>> {
+    Grid::parse(input, |c| c.to_digit(10).map(|d| d as usize))
+}
+
+/// How the crucible is allowed to move.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rules {
+    /// How far the crucible must go in a straight line before it can turn or
+    /// stop.
+    pub min_straight: usize,
+    pub max_straight: usize,
+    /// Whether it can turn straight back the way it came.
+    pub reverse: bool,
+    /// Whether it can also move diagonally.
+    pub diagonal: bool,
+    /// Defaults to the top left corner.
+    pub start: Option<IVec2>,
+    /// Defaults to the bottom right corner.
+    pub end: Option<IVec2>,
+    /// Extra heat lost for each block entered in each of [`ALL_DIRECTIONS`],
+    /// on top of the block's own.
+    pub direction_costs: [usize; 8],
+}
+
+impl Rules {
+    pub const CRUCIBLE: Rules = Rules::new(0, 3);
+    pub const ULTRA: Rules = Rules::new(4, 10);
+
+    pub const fn new(min_straight: usize, max_straight: usize) -> Self {
+        Rules {
+            min_straight,
+            max_straight,
+            reverse: false,
+            diagonal: false,
+            start: None,
+            end: None,
+            direction_costs: [0; 8],
+        }
+    }
+
+    pub fn directions(&self) -> &'static [IVec2] {
+        if self.diagonal {
+            &ALL_DIRECTIONS
+        } else {
+            &ORTHOGONAL
+        }
+    }
+
+    pub fn direction_cost(&self, d: IVec2) -> usize {
+        ALL_DIRECTIONS
+            .iter()
+            .position(|&e| e == d)
+            .map_or(0, |i| self.direction_costs[i])
+    }
+}
+
+/// The cheapest route for the crucible.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Route {
+    pub heat_loss: usize,
+    /// Every cell entered after leaving the start, with the direction it was
+    /// entered in.
+    pub steps: Vec<(IVec2, IVec2)>,
 }
 
-fn run(grid: Vec<Vec<usize>>, min_straight: usize, max_straight: usize) -> usize {
-    let height = grid.len() as i32;
-    let width = grid[0].len() as i32;
-    let mut heap: BTreeSet<_> = [(0, (0, 0), (1, 0)), (0, (0, 0), (0, 1))].into();
-    let mut seen = HashSet::new();
-    while let Some((cost, (x, y), (dx, dy))) = heap.pop_first() {
-        if (x, y) == (width - 1, height - 1) {
-            return cost;
+impl Route {
+    /// The straight runs making up the route, as a direction and a length.
+    pub fn turns(&self) -> Vec<(IVec2, usize)> {
+        let mut turns: Vec<(IVec2, usize)> = Vec::new();
+        for &(_, d) in &self.steps {
+            match turns.last_mut() {
+                Some((last, len)) if *last == d => *len += 1,
+                _ => turns.push((d, 1)),
+            }
+        }
+        turns
+    }
+
+    /// Draws the route over the grid, with an arrow on each cell entered.
+    pub fn render(&self, grid: &Grid<usize>) -> String {
+        let mut cells = grid.map(|&tile| char::from_digit(tile as u32, 10).unwrap_or('?'));
+        for &(p, d) in &self.steps {
+            cells[p] = arrow(d);
         }
-        if !seen.insert(((x, y), (dx, dy))) {
-            continue;
+        cells.to_string()
+    }
+}
+
+fn arrow(d: IVec2) -> char {
+    match (d.x, d.y) {
+        (1, 0) => '>',
+        (-1, 0) => '<',
+        (0, 1) => 'v',
+        (0, -1) => '^',
+        (1, 1) | (-1, -1) => '\\',
+        _ => '/',
+    }
+}
+
+pub fn find_route(grid: &Grid<usize>, rules: &Rules) -> Result<Route> {
+    let start = rules.start.unwrap_or(IVec2::ZERO);
+    let end = rules.end.unwrap_or(grid.size() - 1);
+    for (name, p) in [("start", start), ("end", end)] {
+        if !grid.contains(p) {
+            return Err(Error::solve(format!("the {name} {p} is outside the grid")));
         }
-        for (dx, dy) in [(dy, -dx), (-dy, dx)] {
-            let (mut x, mut y) = (x, y);
-            let mut cost = cost;
-            for i in 1..=max_straight {
-                x += dx;
-                y += dy;
-                if !(0..height).contains(&y) || !(0..width).contains(&x) {
+    }
+    let directions = rules.directions();
+    // The state is where the crucible is and which way it went to get there,
+    // with no direction before the first move.
+    let successors = |&(p, d): &(IVec2, IVec2)| {
+        let mut moves = Vec::new();
+        for &e in directions {
+            if e == d || (e == -d && !rules.reverse) {
+                continue;
+            }
+            let (mut p, mut cost) = (p, 0);
+            for i in 1..=rules.max_straight {
+                p += e;
+                let Some(&tile) = grid.get(p) else {
                     break;
-                }
-                cost += grid[y as usize][x as usize];
-                if i >= min_straight {
-                    heap.insert((cost, (x, y), (dx, dy)));
+                };
+                cost += tile + rules.direction_cost(e);
+                if i >= rules.min_straight {
+                    moves.push(((p, e), cost));
                 }
             }
         }
+        moves
+    };
+    // No step can lose less heat than the cheapest block in the cheapest
+    // direction, or cover more than one row and column.
+    let cheapest = grid.values().min().copied().unwrap_or(0)
+        + directions
+            .iter()
+            .map(|&d| rules.direction_cost(d))
+            .min()
+            .unwrap_or(0);
+    let heuristic = |&(p, _): &(IVec2, IVec2)| {
+        let d = (end - p).abs();
+        let steps = if rules.diagonal {
+            d.max_element()
+        } else {
+            d.x + d.y
+        };
+        steps as usize * cheapest
+    };
+    let path = astar([(start, IVec2::ZERO)], successors, heuristic, |&(p, _)| {
+        p == end
+    })
+    .ok_or_else(|| Error::solve("no route reaches the end"))?;
+    // Each move in the search is a whole straight run, so fill in the cells
+    // along it.
+    let mut steps = Vec::new();
+    for pair in path.states.windows(2) {
+        let ((mut p, _), (to, d)) = (pair[0], pair[1]);
+        while p != to {
+            p += d;
+            steps.push((p, d));
+        }
     }
-    panic!()
+    Ok(Route {
+        heat_loss: path.cost,
+        steps,
+    })
 }
 
-pub fn solve(input: &str) -> usize {
-    run(parse(input), 0, 3)
+pub fn run(grid: &Grid<usize>, rules: &Rules) -> Result<usize> {
+    find_route(grid, rules).map(|route| route.heat_loss)
 }
 
-pub fn solve_2(input: &str) -> usize {
-    run(parse(input), 4, 10)
+pub struct Day17;
+
+impl Solution for Day17 {
+    const DAY: u32 = 17;
+    const TITLE: Option<&'static str> = Some("Clumsy Crucible");
+
+    type Input<'a> = Grid<usize>;
+
+    fn parse(input: &str) -> Result<Self::Input<'_>> {
+        parse(input)
+    }
+
+    fn part1(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        Ok(run(input, &Rules::CRUCIBLE)?.into())
+    }
+
+    fn part2(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        Ok(run(input, &Rules::ULTRA)?.into())
+    }
 }
 
 #[cfg(test)]
@@ -73,12 +218,12 @@ mod test {
 
     #[test]
     fn test_sample() {
-        assert_eq!(solve(SAMPLE), 102);
+        assert_eq!(Day17::solve(SAMPLE).unwrap(), 102);
     }
 
     #[test]
     fn test_sample_2() {
-        assert_eq!(solve_2(SAMPLE), 94);
+        assert_eq!(Day17::solve_2(SAMPLE).unwrap(), 94);
     }
 
     #[test]
@@ -89,6 +234,67 @@ mod test {
         999999999991
         999999999991
         999999999991";
-        assert_eq!(solve_2(SAMPLE), 71);
+        assert_eq!(Day17::solve_2(SAMPLE).unwrap(), 71);
+    }
+
+    #[test]
+    fn test_route() {
+        let grid = parse(SAMPLE).unwrap();
+        let route = find_route(&grid, &Rules::CRUCIBLE).unwrap();
+        assert_eq!(
+            route.steps.iter().map(|&(p, _)| grid[p]).sum::<usize>(),
+            102
+        );
+        assert_eq!(route.steps.last().unwrap().0, grid.size() - 1);
+        assert!(route.turns().iter().all(|&(_, len)| len <= 3));
+
+        const SMALL: &str = "
+        111111111111
+        999999999991
+        999999999991
+        999999999991
+        999999999991";
+        let grid = parse(SMALL).unwrap();
+        let route = find_route(&grid, &Rules::ULTRA).unwrap();
+        assert_eq!(route.turns(), [(IVec2::X, 7), (IVec2::Y, 4), (IVec2::X, 4)]);
+        assert_eq!(
+            route.render(&grid),
+            "1>>>>>>>1111\n\
+             9999999v9991\n\
+             9999999v9991\n\
+             9999999v9991\n\
+             9999999v>>>>"
+        );
+    }
+
+    #[test]
+    fn test_rules() {
+        let grid = parse("19\n11").unwrap();
+        assert_eq!(run(&grid, &Rules::CRUCIBLE).unwrap(), 2);
+        let diagonal = Rules {
+            diagonal: true,
+            ..Rules::CRUCIBLE
+        };
+        assert_eq!(run(&grid, &diagonal).unwrap(), 1);
+        let backwards = Rules {
+            start: Some(IVec2::new(1, 1)),
+            end: Some(IVec2::ZERO),
+            ..Rules::CRUCIBLE
+        };
+        assert_eq!(run(&grid, &backwards).unwrap(), 2);
+        let mut downhill = Rules::CRUCIBLE;
+        downhill.direction_costs[2] = 10;
+        assert_eq!(run(&grid, &downhill).unwrap(), 12);
+
+        // Going right at least twice overshoots, so the only way to stop on
+        // the second block is to come back.
+        let grid = parse("11111").unwrap();
+        let mut rules = Rules::new(2, 3);
+        rules.end = Some(IVec2::X);
+        assert!(run(&grid, &rules).is_err());
+        rules.reverse = true;
+        assert_eq!(run(&grid, &rules).unwrap(), 5);
+        rules.end = Some(IVec2::new(5, 0));
+        assert!(run(&grid, &rules).is_err());
     }
 }
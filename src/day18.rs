@@ -1,23 +1,32 @@
 use nom::{
     bytes::complete::tag,
-    character::complete::{alphanumeric1, digit1, multispace0, one_of, space0},
-    combinator::map_res,
+    character::complete::{digit1, hex_digit1, multispace0, one_of, space0},
+    combinator::{map_res, verify},
     multi::many1,
     sequence::{delimited, preceded, tuple},
     IResult,
 };
 
-fn parse(input: &str) -> Vec<(char, i64, &str)> {
+use crate::solution::{parse_all, Answer, Log, Result, Solution};
+
+pub fn parse(input: &str) -> Result<Vec<(char, i64, &str)>> {
     fn number(input: &str) -> IResult<&str, i64> {
         map_res(digit1, str::parse)(input)
     }
-    many1(tuple((
-        preceded(multispace0, one_of("RDLU")),
-        preceded(space0, number),
-        preceded(space0, delimited(tag("(#"), alphanumeric1, tag(")"))),
-    )))(input)
-    .unwrap()
-    .1
+    // Five hex digits of length, then one of the four directions.
+    fn colour(input: &str) -> IResult<&str, &str> {
+        verify(hex_digit1, |s: &str| {
+            s.len() == 6 && s.ends_with(['0', '1', '2', '3'])
+        })(input)
+    }
+    parse_all(
+        input,
+        many1(tuple((
+            preceded(multispace0, one_of("RDLU")),
+            preceded(space0, number),
+            preceded(space0, delimited(tag("(#"), colour, tag(")"))),
+        ))),
+    )
 }
 
 pub fn run(instrs: impl Iterator<Item = (char, i64)>) -> i64 {
@@ -30,7 +39,7 @@ pub fn run(instrs: impl Iterator<Item = (char, i64)>) -> i64 {
             'D' => (0, 1),
             'L' => (-1, 0),
             'U' => (0, -1),
-            _ => panic!(),
+            _ => unreachable!(),
         };
         let (x2, y2) = (x + dx * len, y + dy * len);
         total_area += len + (y + y2) * (x - x2);
@@ -39,15 +48,29 @@ pub fn run(instrs: impl Iterator<Item = (char, i64)>) -> i64 {
     total_area / 2 + 1
 }
 
-pub fn solve(input: &str) -> i64 {
-    run(parse(input).into_iter().map(|(dir, len, _)| (dir, len)))
-}
+pub struct Day18;
+
+impl Solution for Day18 {
+    const DAY: u32 = 18;
+    const TITLE: Option<&'static str> = Some("Lavaduct Lagoon");
+
+    type Input<'a> = Vec<(char, i64, &'a str)>;
 
-pub fn solve_2(input: &str) -> i64 {
-    run(parse(input).into_iter().map(|(.., s)| {
-        let code = i64::from_str_radix(s, 16).unwrap();
-        (['R', 'D', 'L', 'U'][(code % 16) as usize], code / 16)
-    }))
+    fn parse(input: &str) -> Result<Self::Input<'_>> {
+        parse(input)
+    }
+
+    fn part1(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        Ok(run(input.iter().map(|&(dir, len, _)| (dir, len))).into())
+    }
+
+    fn part2(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        Ok(run(input.iter().map(|&(.., s)| {
+            let code = i64::from_str_radix(s, 16).unwrap();
+            (['R', 'D', 'L', 'U'][(code % 16) as usize], code / 16)
+        }))
+        .into())
+    }
 }
 
 #[cfg(test)]
@@ -72,11 +95,11 @@ mod test {
 
     #[test]
     fn test_sample() {
-        assert_eq!(solve(SAMPLE), 62);
+        assert_eq!(Day18::solve(SAMPLE).unwrap(), 62);
     }
 
     #[test]
     fn test_sample_2() {
-        assert_eq!(solve_2(SAMPLE), 952408144115);
+        assert_eq!(Day18::solve_2(SAMPLE).unwrap(), 952408144115);
     }
 }
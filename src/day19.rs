@@ -1,29 +1,118 @@
 use std::collections::HashMap;
 
 use nom::{
+    branch::alt,
     bytes::complete::tag,
-    character::complete::{alpha1, digit1, multispace0, one_of},
-    combinator::{map, map_res},
+    character::complete::{alpha1, digit1, multispace0},
+    combinator::{map_res, value},
     multi::{many0, many1, separated_list1},
     sequence::{delimited, pair, preceded, terminated, tuple},
     IResult,
 };
 
-fn parse(
-    input: &str,
-) -> (
-    HashMap<&str, (Vec<(char, char, usize, &str)>, &str)>,
-    Vec<[usize; 4]>,
-) {
+use crate::{
+    solution::{parse_all, Answer, Error, Log, Result, Solution},
+    utils::intervals::IntervalSet,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl Op {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Eq => "==",
+            Op::Ne => "!=",
+        }
+    }
+
+    pub fn holds(self, lhs: usize, rhs: usize) -> bool {
+        match self {
+            Op::Lt => lhs < rhs,
+            Op::Le => lhs <= rhs,
+            Op::Gt => lhs > rhs,
+            Op::Ge => lhs >= rhs,
+            Op::Eq => lhs == rhs,
+            Op::Ne => lhs != rhs,
+        }
+    }
+
+    /// Every value that compares with `rhs` as this says.
+    pub fn passing(self, rhs: usize) -> IntervalSet {
+        // No set of values reaches `i64::MAX`, so anything from there up
+        // compares the same as it.
+        let rhs = i64::try_from(rhs).unwrap_or(i64::MAX);
+        let after = rhs.saturating_add(1);
+        match self {
+            Op::Lt => (i64::MIN..rhs).into(),
+            Op::Le => (i64::MIN..after).into(),
+            Op::Gt => (after..i64::MAX).into(),
+            Op::Ge => (rhs..i64::MAX).into(),
+            Op::Eq => (rhs..after).into(),
+            Op::Ne => [i64::MIN..rhs, after..i64::MAX].into_iter().collect(),
+        }
+    }
+}
+
+/// Compares the rating in a category, an index into
+/// [`System::categories`], with a constant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Comparison {
+    pub category: usize,
+    pub op: Op,
+    pub value: usize,
+}
+
+/// Sends a part to `target` if it passes every comparison in any one of the
+/// terms of `condition`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rule<'a> {
+    pub condition: Vec<Vec<Comparison>>,
+    pub target: &'a str,
+}
+
+pub type Workflows<'a> = HashMap<&'a str, (Vec<Rule<'a>>, &'a str)>;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct System<'a> {
+    /// Every category named in the input: the ones the parts rate, in order of
+    /// first appearance there, then any only the workflows test, in the order
+    /// they come in the workflows.
+    pub categories: Vec<&'a str>,
+    pub workflows: Workflows<'a>,
+    /// The ratings of each part, one per category, with 0 for any the part
+    /// doesn't list.
+    pub parts: Vec<Vec<usize>>,
+}
+
+pub fn parse(input: &str) -> Result<System<'_>> {
     fn number(input: &str) -> IResult<&str, usize> {
         map_res(digit1, str::parse)(input)
     }
-    let rule = tuple((
-        one_of("xmas"),
-        one_of("<>"),
-        number,
-        preceded(tag(":"), alpha1),
+    let op = alt((
+        value(Op::Le, tag("<=")),
+        value(Op::Ge, tag(">=")),
+        value(Op::Eq, tag("==")),
+        value(Op::Ne, tag("!=")),
+        value(Op::Lt, tag("<")),
+        value(Op::Gt, tag(">")),
     ));
+    let condition = separated_list1(
+        tag("||"),
+        separated_list1(tag("&&"), tuple((alpha1, op, number))),
+    );
+    let rule = pair(condition, preceded(tag(":"), alpha1));
     let workflow = pair(
         alpha1,
         delimited(
@@ -34,98 +123,318 @@ fn parse(
     );
     let part = delimited(
         tag("{"),
-        map(
-            separated_list1(tag(","), pair(one_of("xmas"), preceded(tag("="), number))),
-            |catergories| {
-                let mut array = [0; 4];
-                for (c, n) in catergories {
-                    array["xmas".find(c).unwrap()] = n;
-                }
-                array
-            },
-        ),
+        separated_list1(tag(","), pair(alpha1, preceded(tag("="), number))),
         tag("}"),
     );
-    let (_, (workflows, parts)) = pair(
-        map(many1(preceded(multispace0, workflow)), |workflows| {
-            workflows.into_iter().collect::<HashMap<_, _>>()
-        }),
-        many1(preceded(multispace0, part)),
-    )(input)
-    .unwrap();
-    (workflows, parts)
-}
+    let (workflows, parts) = parse_all(
+        input,
+        pair(
+            many1(preceded(multispace0, workflow)),
+            many1(preceded(multispace0, part)),
+        ),
+    )?;
 
-fn is_accepted<'a>(
-    workflows: HashMap<&'a str, (Vec<(char, char, usize, &'a str)>, &'a str)>,
-) -> impl Fn(&[usize; 4]) -> bool + 'a {
-    fn resolve_workflow<'a>(
-        part: &[usize; 4],
-        rules: &Vec<(char, char, usize, &'a str)>,
-        default: &'a str,
-    ) -> &'a str {
-        for &(lhs, op, rhs, res) in rules {
-            let i = "xmas".find(lhs).unwrap();
-            match op {
-                '<' if part[i] < rhs => return res,
-                '>' if part[i] > rhs => return res,
-                _ => (),
-            }
+    for (i, &(name, _)) in workflows.iter().enumerate() {
+        if workflows[..i].iter().any(|&(earlier, _)| earlier == name) {
+            return Err(Error::parse(input, name, "duplicate workflow"));
         }
-        default
     }
-    move |part| {
-        let mut workflow = "in";
-        while let Some((rules, default)) = workflows.get(workflow) {
-            workflow = resolve_workflow(part, rules, default);
+
+    let mut categories = Vec::new();
+    let mut category = |name| match categories.iter().position(|&c| c == name) {
+        Some(i) => i,
+        None => {
+            categories.push(name);
+            categories.len() - 1
+        }
+    };
+    let parts: Vec<Vec<(usize, usize)>> = parts
+        .into_iter()
+        .map(|part| part.into_iter().map(|(c, n)| (category(c), n)).collect())
+        .collect();
+    let workflows: Workflows = workflows
+        .into_iter()
+        .map(|(name, (rules, default))| {
+            let rules = rules
+                .into_iter()
+                .map(|(condition, target)| Rule {
+                    condition: condition
+                        .into_iter()
+                        .map(|term| {
+                            term.into_iter()
+                                .map(|(c, op, value)| Comparison {
+                                    category: category(c),
+                                    op,
+                                    value,
+                                })
+                                .collect()
+                        })
+                        .collect(),
+                    target,
+                })
+                .collect();
+            (name, (rules, default))
+        })
+        .collect();
+    let parts = parts
+        .into_iter()
+        .map(|ratings| {
+            let mut part = vec![0; categories.len()];
+            for (c, n) in ratings {
+                part[c] = n;
+            }
+            part
+        })
+        .collect();
+
+    let targets = workflows
+        .values()
+        .flat_map(|(rules, default)| rules.iter().map(|rule| rule.target).chain([*default]));
+    for target in targets {
+        if !matches!(target, "A" | "R") && !workflows.contains_key(target) {
+            return Err(Error::parse(input, target, "unknown workflow"));
         }
-        workflow == "A"
     }
+    if !workflows.contains_key("in") {
+        return Err(Error::solve("no workflow named in"));
+    }
+    Ok(System {
+        categories,
+        workflows,
+        parts,
+    })
 }
 
-pub fn solve(input: &str) -> usize {
-    let (workflows, parts) = parse(input);
-    parts
-        .into_iter()
-        .filter(is_accepted(workflows))
-        .flatten()
-        .sum()
+/// The ratings of a set of parts, one set of values per category.
+pub type Parts = Vec<IntervalSet>;
+
+pub fn volume(parts: &Parts) -> usize {
+    parts.iter().map(|values| values.len() as usize).product()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Node<'a> {
+    Accept,
+    Reject,
+    /// A comparison from `workflow`, going to `pass` if the part passes it
+    /// and to `fail` otherwise.
+    Test {
+        workflow: &'a str,
+        comparison: Comparison,
+        pass: usize,
+        fail: usize,
+    },
+}
+
+impl Node<'_> {
+    /// Splits `parts` into those that pass the test and those that fail it.
+    fn split(&self, parts: &Parts) -> (Parts, Parts) {
+        let &Node::Test {
+            comparison:
+                Comparison {
+                    category,
+                    op,
+                    value,
+                },
+            ..
+        } = self
+        else {
+            panic!("only tests split parts");
+        };
+        let passing = op.passing(value);
+        let (mut pass, mut fail) = (parts.clone(), parts.clone());
+        pass[category] = parts[category].intersection(&passing);
+        fail[category] = parts[category].difference(&passing);
+        (pass, fail)
+    }
 }
 
-fn count_parts(
-    workflows: &HashMap<&str, (Vec<(char, char, usize, &str)>, &str)>,
-    start: &str,
-    mut rem: [(usize, usize); 4],
-) -> usize {
-    match start {
-        "A" => return rem.into_iter().map(|(min, max)| 1 + max - min).product(),
-        "R" => return 0,
-        _ => (),
-    }
-    let mut total = 0;
-    let (rules, default) = workflows.get(start).unwrap();
-    for &(lhs, op, rhs, res) in rules {
-        let i = "xmas".find(lhs).unwrap();
-        let mut parts = rem;
-        match op {
-            '<' => {
-                parts[i].1 = rhs - 1;
-                rem[i].0 = rhs;
+/// The workflows compiled into one graph of single comparisons, which parts
+/// follow from `root` until they are accepted or rejected.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecisionTree<'a> {
+    pub categories: Vec<&'a str>,
+    pub nodes: Vec<Node<'a>>,
+    pub root: usize,
+}
+
+impl<'a> DecisionTree<'a> {
+    const ACCEPT: usize = 0;
+    const REJECT: usize = 1;
+
+    pub fn compile(system: &System<'a>) -> Result<Self> {
+        let mut tree = DecisionTree {
+            categories: system.categories.clone(),
+            nodes: vec![Node::Accept, Node::Reject],
+            root: DecisionTree::REJECT,
+        };
+        let mut entries = HashMap::new();
+        tree.root = tree.compile_workflow(&system.workflows, "in", &mut entries)?;
+        Ok(tree)
+    }
+
+    /// Adds the rules of `name` and every workflow it leads to, returning
+    /// its first node. `entries` holds the first node of each workflow
+    /// compiled so far, or `None` while it's still being compiled.
+    fn compile_workflow(
+        &mut self,
+        workflows: &Workflows<'a>,
+        name: &'a str,
+        entries: &mut HashMap<&'a str, Option<usize>>,
+    ) -> Result<usize> {
+        match name {
+            "A" => return Ok(DecisionTree::ACCEPT),
+            "R" => return Ok(DecisionTree::REJECT),
+            _ => (),
+        }
+        match entries.get(name) {
+            Some(&Some(entry)) => return Ok(entry),
+            Some(None) => {
+                return Err(Error::solve(format!(
+                    "workflow {name} loops back to itself"
+                )))
+            }
+            None => (),
+        }
+        entries.insert(name, None);
+        let (rules, default) = &workflows[name];
+        let mut next = self.compile_workflow(workflows, default, entries)?;
+        for rule in rules.iter().rev() {
+            let pass = self.compile_workflow(workflows, rule.target, entries)?;
+            // A part that fails every comparison in a term moves on to the
+            // next term, and only fails the rule after the last one.
+            for term in rule.condition.iter().rev() {
+                let mut term_pass = pass;
+                for &comparison in term.iter().rev() {
+                    self.nodes.push(Node::Test {
+                        workflow: name,
+                        comparison,
+                        pass: term_pass,
+                        fail: next,
+                    });
+                    term_pass = self.nodes.len() - 1;
+                }
+                next = term_pass;
+            }
+        }
+        entries.insert(name, Some(next));
+        Ok(next)
+    }
+
+    pub fn accepts(&self, part: &[usize]) -> bool {
+        let mut node = self.root;
+        loop {
+            match self.nodes[node] {
+                Node::Accept => return true,
+                Node::Reject => return false,
+                Node::Test {
+                    comparison:
+                        Comparison {
+                            category,
+                            op,
+                            value,
+                        },
+                    pass,
+                    fail,
+                    ..
+                } => {
+                    let passes = op.holds(part[category], value);
+                    node = if passes { pass } else { fail };
+                }
+            }
+        }
+    }
+
+    /// Every possible rating of every category.
+    pub fn all_parts(&self) -> Parts {
+        vec![(1..4001).into(); self.categories.len()]
+    }
+
+    /// Splits `parts` into the disjoint boxes of parts that are accepted.
+    pub fn accepted(&self, parts: Parts) -> Vec<Parts> {
+        let mut accepted = Vec::new();
+        let mut stack = vec![(self.root, parts)];
+        while let Some((node, parts)) = stack.pop() {
+            if parts.iter().any(IntervalSet::is_empty) {
+                continue;
+            }
+            match self.nodes[node] {
+                Node::Accept => accepted.push(parts),
+                Node::Reject => (),
+                test @ Node::Test { pass, fail, .. } => {
+                    let (passed, failed) = test.split(&parts);
+                    stack.push((fail, failed));
+                    stack.push((pass, passed));
+                }
             }
-            '>' => {
-                parts[i].0 = rhs + 1;
-                rem[i].1 = rhs;
+        }
+        accepted
+    }
+
+    /// Counts the combinations of ratings in `parts` that are accepted.
+    pub fn count(&self, parts: Parts) -> usize {
+        self.accepted(parts).iter().map(volume).sum()
+    }
+
+    /// Writes the tree as a Graphviz graph.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph workflows {\n");
+        for (i, node) in self.nodes.iter().enumerate() {
+            match *node {
+                Node::Accept => {
+                    dot += &format!("    n{i} [label=\"A\", shape=box, color=green];\n")
+                }
+                Node::Reject => dot += &format!("    n{i} [label=\"R\", shape=box, color=red];\n"),
+                Node::Test {
+                    workflow,
+                    comparison,
+                    pass,
+                    fail,
+                } => {
+                    let category = self.categories[comparison.category];
+                    let op = comparison.op.symbol();
+                    let value = comparison.value;
+                    dot += &format!("    n{i} [label=\"{workflow}: {category}{op}{value}\"];\n");
+                    dot += &format!("    n{i} -> n{pass} [label=\"yes\"];\n");
+                    dot += &format!("    n{i} -> n{fail} [label=\"no\", style=dashed];\n");
+                }
             }
-            _ => unreachable!(),
         }
-        total += count_parts(workflows, res, parts);
+        dot += &format!(
+            "    start [shape=point];\n    start -> n{};\n}}\n",
+            self.root
+        );
+        dot
     }
-    total + count_parts(workflows, default, rem)
 }
 
-pub fn solve_2(input: &str) -> usize {
-    let (workflows, _) = parse(input);
-    count_parts(&workflows, "in", [(1, 4000); 4])
+pub struct Day19;
+
+impl Solution for Day19 {
+    const DAY: u32 = 19;
+    const TITLE: Option<&'static str> = Some("Aplenty");
+
+    type Input<'a> = System<'a>;
+
+    fn parse(input: &str) -> Result<Self::Input<'_>> {
+        parse(input)
+    }
+
+    fn part1(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        let tree = DecisionTree::compile(input)?;
+        Ok(input
+            .parts
+            .iter()
+            .filter(|part| tree.accepts(part))
+            .flatten()
+            .sum::<usize>()
+            .into())
+    }
+
+    fn part2(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        let tree = DecisionTree::compile(input)?;
+        Ok(tree.count(tree.all_parts()).into())
+    }
 }
 
 #[cfg(test)]
@@ -153,11 +462,90 @@ mod test {
 
     #[test]
     fn test_sample() {
-        assert_eq!(solve(SAMPLE), 19114);
+        assert_eq!(Day19::solve(SAMPLE).unwrap(), 19114);
     }
 
     #[test]
     fn test_sample_2() {
-        assert_eq!(solve_2(SAMPLE), 167409079868000);
+        assert_eq!(Day19::solve_2(SAMPLE).unwrap(), 167409079868000);
+    }
+
+    #[test]
+    fn test_decision_tree() {
+        let system = parse(SAMPLE).unwrap();
+        assert_eq!(system.categories, ["x", "m", "a", "s"]);
+        let tree = DecisionTree::compile(&system).unwrap();
+        // One test per rule, plus accept and reject.
+        assert_eq!(tree.nodes.len(), 2 + 14);
+        let accepted = tree.accepted(tree.all_parts());
+        let total: usize = accepted.iter().map(volume).sum();
+        assert_eq!(total, 167409079868000);
+        for (i, a) in accepted.iter().enumerate() {
+            for b in &accepted[i + 1..] {
+                assert!((0..4).any(|c| a[c].intersection(&b[c]).is_empty()));
+            }
+        }
+
+        let dot = tree.to_dot();
+        assert!(dot.starts_with("digraph workflows {"));
+        assert!(dot.contains("[label=\"in: s<1351\"]"));
+
+        let system = parse("in{x<5:a,A}\na{R}\n{x=1}").unwrap();
+        assert!(DecisionTree::compile(&system).is_ok());
+        let system = parse("in{x<5:a,A}\na{m>3:in,R}\n{x=1}").unwrap();
+        assert!(DecisionTree::compile(&system).is_err());
+    }
+
+    #[test]
+    fn test_rule_language() {
+        const SAMPLE: &str = "
+        in{hp>=5&&mp!=3:A,hp==2||mp<=1:ok,R}
+        ok{mp>8:R,A}
+
+        {hp=5,mp=3}
+        {hp=2,mp=9}
+        {mp=1}
+        {hp=7,mp=4}";
+        let system = parse(SAMPLE).unwrap();
+        assert_eq!(system.categories, ["hp", "mp"]);
+        assert_eq!(system.parts[2], [0, 1]);
+        let ordered = parse("in{z>1&&m<2:A,x<5:R,A}\n{x=1}\n{m=2,x=3}").unwrap();
+        assert_eq!(ordered.categories, ["x", "m", "z"]);
+        assert_eq!(ordered.parts, [[1, 0, 0], [3, 2, 0]]);
+        assert_eq!(Day19::solve(SAMPLE).unwrap(), 12);
+
+        // Check the counting against every part with ratings up to 10.
+        let tree = DecisionTree::compile(&system).unwrap();
+        let brute_force = (1..=10)
+            .flat_map(|hp| (1..=10).map(move |mp| [hp, mp]))
+            .filter(|part| tree.accepts(part))
+            .count();
+        assert_eq!(tree.count(vec![(1..11).into(); 2]), brute_force);
+        assert_eq!(brute_force, 6 * 9 + 8 + 3);
+
+        // Values too big for the ratings still compare correctly.
+        let huge = usize::MAX;
+        assert_eq!(Op::Lt.passing(huge), (i64::MIN..i64::MAX).into());
+        assert_eq!(Op::Ne.passing(huge), (i64::MIN..i64::MAX).into());
+        assert!(Op::Gt.passing(huge).is_empty());
+        assert!(Op::Eq.passing(huge).is_empty());
+        let input = format!("in{{x<{huge}:A,R}}\n{{x=1}}");
+        let system = parse(&input).unwrap();
+        let tree = DecisionTree::compile(&system).unwrap();
+        assert_eq!(tree.count(tree.all_parts()), 4000);
+    }
+
+    #[test]
+    fn test_duplicate_workflow() {
+        let error = parse("in{x<5:A,R}\nin{R}\n{x=1}").unwrap_err();
+        assert_eq!(
+            error,
+            Error::Parse {
+                line: 2,
+                column: 1,
+                text: "in{R}".to_string(),
+                message: "duplicate workflow".to_string(),
+            }
+        );
     }
 }
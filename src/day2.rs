@@ -4,12 +4,15 @@ use nom::{
     bytes::complete::tag,
     character::complete::{alpha1, digit1, multispace0, space1},
     combinator::map_res,
+    error::{Error, ErrorKind},
     multi::{many1, separated_list1},
     sequence::{delimited, pair, preceded},
     IResult,
 };
 
-fn parse(input: &str) -> Vec<(usize, Vec<[usize; 3]>)> {
+use crate::solution::{parse_all, Answer, Log, Result, Solution};
+
+pub fn parse(input: &str) -> Result<Vec<(usize, Vec<[usize; 3]>)>> {
     fn number(input: &str) -> IResult<&str, usize> {
         map_res(digit1, str::parse)(input)
     }
@@ -21,7 +24,7 @@ fn parse(input: &str) -> Vec<(usize, Vec<[usize; 3]>)> {
                 "red" => 0,
                 "green" => 1,
                 "blue" => 2,
-                _ => panic!(),
+                _ => return Err(nom::Err::Failure(Error::new(kind, ErrorKind::Tag))),
             };
             result[i] = count;
             input = remainder;
@@ -32,45 +35,62 @@ fn parse(input: &str) -> Vec<(usize, Vec<[usize; 3]>)> {
         }
         Ok((input, result))
     }
-    many1(preceded(
-        multispace0,
-        pair(
-            delimited(tag("Game "), number, tag(": ")),
-            separated_list1(tag("; "), cube_set),
-        ),
-    ))(input)
-    .unwrap()
-    .1
+    parse_all(
+        input,
+        many1(preceded(
+            multispace0,
+            pair(
+                delimited(tag("Game "), number, tag(": ")),
+                separated_list1(tag("; "), cube_set),
+            ),
+        )),
+    )
 }
 
-pub fn solve(input: &str) -> usize {
-    let target = [12, 13, 14];
-    parse(input)
-        .into_iter()
-        .filter_map(|(id, counts)| {
-            if counts
-                .into_iter()
-                .all(|count| zip(count, target).all(|(a, b)| a <= b))
-            {
-                Some(id)
-            } else {
-                None
-            }
-        })
-        .sum()
-}
+pub struct Day2;
+
+impl Solution for Day2 {
+    const DAY: u32 = 2;
+    const TITLE: Option<&'static str> = Some("Cube Conundrum");
+
+    type Input<'a> = Vec<(usize, Vec<[usize; 3]>)>;
+
+    fn parse(input: &str) -> Result<Self::Input<'_>> {
+        parse(input)
+    }
 
-pub fn solve_2(input: &str) -> usize {
-    parse(input)
-        .into_iter()
-        .map(|(_, counts)| {
-            let min_required = counts
-                .into_iter()
-                .reduce(|a, b| [a[0].max(b[0]), a[1].max(b[1]), a[2].max(b[2])])
-                .unwrap();
-            min_required.into_iter().product::<usize>()
-        })
-        .sum()
+    fn part1(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        let target = [12, 13, 14];
+        Ok(input
+            .iter()
+            .filter_map(|(id, counts)| {
+                if counts
+                    .iter()
+                    .all(|&count| zip(count, target).all(|(a, b)| a <= b))
+                {
+                    Some(id)
+                } else {
+                    None
+                }
+            })
+            .sum::<usize>()
+            .into())
+    }
+
+    fn part2(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        Ok(input
+            .iter()
+            .map(|(_, counts)| {
+                let min_required = counts
+                    .iter()
+                    .copied()
+                    .reduce(|a, b| [a[0].max(b[0]), a[1].max(b[1]), a[2].max(b[2])])
+                    .unwrap();
+                min_required.into_iter().product::<usize>()
+            })
+            .sum::<usize>()
+            .into())
+    }
 }
 
 #[cfg(test)]
@@ -86,11 +106,21 @@ mod test {
 
     #[test]
     fn test_sample_1() {
-        assert_eq!(solve(SAMPLE), 8);
+        assert_eq!(Day2::solve(SAMPLE).unwrap(), 8);
     }
 
     #[test]
     fn test_sample_2() {
-        assert_eq!(solve_2(SAMPLE), 2286);
+        assert_eq!(Day2::solve_2(SAMPLE).unwrap(), 2286);
+    }
+
+    #[test]
+    fn test_bad_colour() {
+        let Err(crate::solution::Error::Parse { line, column, .. }) =
+            Day2::solve("Game 1: 3 blue\nGame 2: 4 teal")
+        else {
+            panic!()
+        };
+        assert_eq!((line, column), (2, 11));
     }
 }
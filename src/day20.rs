@@ -1,134 +1,268 @@
-use std::collections::{HashMap, VecDeque};
-
-use nom::{
-    bytes::complete::tag,
-    character::complete::{alpha1, multispace0, one_of},
-    combinator::opt,
-    multi::{many1, separated_list1},
-    sequence::{pair, preceded},
-    IResult,
+use std::collections::HashSet;
+
+use crate::{
+    solution::{Answer, Error, Log, Result, Solution},
+    utils::logic::{self, Builtin, Module, Network, Simulator},
 };
 
-fn parse_modules(input: &str) -> HashMap<&str, Module> {
-    fn parse(input: &str) -> IResult<&str, Vec<((Option<char>, &str), Vec<&str>)>> {
-        many1(preceded(
-            multispace0,
-            pair(
-                pair(opt(one_of("%&")), alpha1),
-                preceded(tag(" -> "), separated_list1(tag(", "), alpha1)),
-            ),
-        ))(input)
-    }
-    let (_, modules) = parse(input).unwrap();
-    let mut inputs = HashMap::<&str, Vec<&str>>::new();
-    for ((_, name), dests) in &modules {
-        for dest in dests {
-            inputs.entry(dest).or_default().push(name);
-        }
-    }
-    let mut result = HashMap::new();
-    for ((prefix, name), dests) in modules {
-        let kind = match prefix {
-            Some('%') => Kind::FlipFlop(false),
-            Some('&') => Kind::Conjunction(
-                inputs
-                    .get(name)
-                    .unwrap()
-                    .iter()
-                    .map(|&src| (src, false))
-                    .collect(),
-            ),
-            None => Kind::Broadcast,
-            _ => panic!(),
-        };
-        result.insert(name, Module { kind, dests });
-    }
-    result
+pub fn parse(input: &str) -> Result<Network<'_>> {
+    logic::parse(input)
 }
 
-#[derive(Clone)]
-enum Kind<'a> {
-    Broadcast,
-    FlipFlop(bool),
-    Conjunction(HashMap<&'a str, bool>),
+/// The module that the button sends to.
+pub const TRIGGER: &str = "broadcaster";
+
+/// A part of the network that only the trigger's pulse to `start` reaches,
+/// and that only reaches the rest through `terminal`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Component<'a> {
+    pub start: &'a str,
+    pub terminal: &'a str,
+    /// Every module in the component, in name order.
+    pub modules: Vec<&'a str>,
 }
 
-#[derive(Clone)]
-struct Module<'a> {
-    kind: Kind<'a>,
-    dests: Vec<&'a str>,
+/// How the network is built around `rx`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Analysis<'a> {
+    /// The conjunction that sends to `rx`, which sends a low pulse once every
+    /// component's terminal has sent it a high one.
+    pub sink: &'a str,
+    pub components: Vec<Component<'a>>,
 }
 
-fn run<'a>(
-    modules: &mut HashMap<&'a str, Module<'a>>,
-    start: &'a str,
-    end: Option<&'a str>,
-) -> ([usize; 2], bool) {
-    let mut counts = [0; 2];
-    let mut queue: VecDeque<_> = [(None, false, start)].into();
-    while let Some((src, mut signal, dest)) = queue.pop_front() {
-        counts[signal as usize] += 1;
-        if (signal, Some(dest)) == (false, end) {
-            return (counts, true);
+/// Splits the network into the independent counters that `trigger` starts
+/// and that drive `rx`.
+pub fn analyse<'a>(modules: &Network<'a>, trigger: &str) -> Result<Analysis<'a>> {
+    let feeding: Vec<_> = modules
+        .iter()
+        .filter(|(_, module)| module.dests.contains(&"rx"))
+        .collect();
+    let &[(&sink, sink_module)] = feeding.as_slice() else {
+        return Err(Error::solve(format!(
+            "expected one module to send to rx, found {}",
+            feeding.len()
+        )));
+    };
+    if sink_module.kind.builtin() != Some(Builtin::Conjunction) {
+        return Err(Error::solve(format!(
+            "{sink} sends to rx but isn't a conjunction"
+        )));
+    }
+    let terminals = modules
+        .iter()
+        .filter(|(_, module)| module.dests.contains(&sink))
+        .map(|(name, _)| name);
+    let trigger_module = modules
+        .get(trigger)
+        .ok_or_else(|| Error::solve(format!("no module named {trigger}")))?;
+    let mut components = Vec::new();
+    for &start in &trigger_module.dests {
+        let mut seen = HashSet::from([start]);
+        let mut stack = vec![start];
+        let mut reached = Vec::new();
+        while let Some(name) = stack.pop() {
+            for &dest in modules.get(name).map_or(&[][..], |m| &m.dests) {
+                if dest == sink {
+                    reached.push(name);
+                } else if seen.insert(dest) {
+                    stack.push(dest);
+                }
+            }
         }
-        let Some(module) = modules.get_mut(dest) else {
-            continue;
+        let &[terminal] = reached.as_slice() else {
+            return Err(Error::solve(format!(
+                "the modules after {start} reach {sink} through {} modules, not one",
+                reached.len()
+            )));
         };
-        match &mut module.kind {
-            Kind::FlipFlop(value) if !signal => {
-                (signal, *value) = (!*value, !*value);
-            }
-            Kind::Conjunction(inputs) => {
-                *inputs.get_mut(&src.unwrap()).unwrap() = signal;
-                signal = !inputs.values().all(|&v| v);
+        let mut modules: Vec<_> = seen.into_iter().collect();
+        modules.sort();
+        components.push(Component {
+            start,
+            terminal,
+            modules,
+        });
+    }
+    for (i, a) in components.iter().enumerate() {
+        for b in &components[i + 1..] {
+            if let Some(shared) = a.modules.iter().find(|m| b.modules.contains(m)) {
+                return Err(Error::solve(format!(
+                    "{shared} is reached from both {} and {}",
+                    a.start, b.start
+                )));
             }
-            Kind::Broadcast => (),
-            _ => continue,
-        }
-        for d in &module.dests {
-            queue.push_back((Some(dest), signal, d));
         }
     }
-    (counts, false)
+    let mut unreached: Vec<_> = terminals
+        .filter(|t| !components.iter().any(|c| c.terminal == **t))
+        .collect();
+    unreached.sort();
+    if let Some(terminal) = unreached.first() {
+        return Err(Error::solve(format!(
+            "{terminal} sends to {sink} but isn't reached from {trigger}"
+        )));
+    }
+    Ok(Analysis { sink, components })
 }
 
-pub fn solve(input: &str) -> usize {
-    let mut modules = parse_modules(input);
-    let (mut a1, mut b1) = (0, 0);
-    for _ in 0..1000 {
-        let ([a2, b2], _) = run(&mut modules, "broadcaster", None);
-        (a1, b1) = (a1 + a2, b1 + b2);
+/// Counts the presses until `component`'s terminal first sends a high pulse to
+/// `sink`, checking that it does so again after as many presses again.
+pub fn cycle_length<'a>(
+    modules: &Network<'a>,
+    component: &Component<'a>,
+    sink: &'a str,
+) -> Result<usize> {
+    // Nothing outside the component affects it, and only pulses to the sink
+    // leave it, so it can be simulated alone.
+    let own = component
+        .modules
+        .iter()
+        .filter_map(|&name| Some((name, modules.get(name)?.clone())))
+        .collect();
+    let mut simulator = Simulator::new(own, component.start)?;
+    let fires = |simulator: &mut Simulator<'a>| {
+        let mut fired = false;
+        simulator.press(|pulse| {
+            fired |= pulse.signal && pulse.source == component.terminal && pulse.dest == sink
+        });
+        fired
+    };
+    let mut seen = HashSet::from([simulator.state_key()]);
+    while !fires(&mut simulator) {
+        // Back in an earlier state, the presses only repeat what they did
+        // before, so the terminal will never fire.
+        if !seen.insert(simulator.state_key()) {
+            return Err(Error::solve(format!(
+                "{} never sends a high pulse to {sink}",
+                component.terminal
+            )));
+        }
     }
-    a1 * b1
+    let length = simulator.presses();
+    if (1..length).any(|_| fires(&mut simulator)) || !fires(&mut simulator) {
+        return Err(Error::solve(format!(
+            "{} doesn't send a high pulse to {sink} every {length} presses",
+            component.terminal
+        )));
+    }
+    Ok(length)
 }
 
-pub fn solve_2(input: &str) -> usize {
-    let modules: HashMap<&str, Module<'_>> = parse_modules(input);
-    let gcd = |mut a: usize, mut b: usize| {
-        while b != 0 {
-            (a, b) = (b, a % b);
+/// Writes the network as a Graphviz graph. Each component in `analysis` gets
+/// its own colour, and `states` labels each module with what it remembers.
+pub fn to_dot(modules: &Network, analysis: Option<&Analysis>, states: bool) -> String {
+    const COLOURS: [&str; 8] = [
+        "lightblue",
+        "lightpink",
+        "palegreen",
+        "khaki",
+        "plum",
+        "lightsalmon",
+        "paleturquoise",
+        "wheat",
+    ];
+    let colour = |name| {
+        let analysis = analysis?;
+        if name == analysis.sink {
+            return Some("gray");
         }
-        a
+        let i = analysis
+            .components
+            .iter()
+            .position(|c| c.modules.contains(&name))?;
+        Some(COLOURS[i % COLOURS.len()])
     };
-    let calc_length = |(start, end)| {
-        let mut modules = modules.clone();
-        for i in 1.. {
-            if run(&mut modules, start, Some(end)).1 {
-                return i;
-            }
+    let mut names: Vec<_> = modules
+        .iter()
+        .flat_map(|(&name, module)| module.dests.iter().copied().chain([name]))
+        .collect();
+    names.sort();
+    names.dedup();
+    let mut dot = String::from("digraph modules {\n");
+    for name in names {
+        let Some(Module { kind, .. }) = modules.get(name) else {
+            dot += &format!("    \"{name}\" [shape=plaintext, label=\"{name}\"];\n");
+            continue;
+        };
+        let symbol = kind.symbol();
+        let shape = match kind.builtin() {
+            Some(Builtin::Broadcast) => "diamond",
+            Some(Builtin::FlipFlop) => "box",
+            Some(Builtin::Conjunction | Builtin::And | Builtin::Or | Builtin::Xor) => "ellipse",
+            Some(Builtin::Counter) => "octagon",
+            Some(Builtin::Delay) => "parallelogram",
+            // Kinds from outside the built-in set.
+            None => "hexagon",
+        };
+        let mut label = format!("{symbol}{name}");
+        if let Some(state) = kind.state().filter(|_| states) {
+            label += &format!("\\n{state}");
         }
-        panic!()
-    };
-    // The input's structure looks like four independent components. When the
-    // last module of each component signals false the "rx" module will also
-    // signal false. So the solution is to find the period of each component,
-    // then find the least common multiple of those values. The names of these
-    // modules were found by inspecting the graph.
-    [("nm", "pk"), ("ps", "pm"), ("sh", "hf"), ("fs", "mk")]
-        .into_iter()
-        .map(calc_length)
-        .reduce(|a, b| a * b / gcd(a, b))
-        .unwrap()
+        dot += &format!("    \"{name}\" [shape={shape}, label=\"{label}\"");
+        if let Some(colour) = colour(name) {
+            dot += &format!(", style=filled, fillcolor={colour}");
+        }
+        dot += "];\n";
+    }
+    let mut edges: Vec<_> = modules
+        .iter()
+        .flat_map(|(name, module)| module.dests.iter().map(move |dest| (name, dest)))
+        .collect();
+    edges.sort();
+    for (name, dest) in edges {
+        dot += &format!("    \"{name}\" -> \"{dest}\";\n");
+    }
+    dot += "}\n";
+    dot
+}
+
+pub struct Day20;
+
+impl Solution for Day20 {
+    const DAY: u32 = 20;
+    const TITLE: Option<&'static str> = Some("Pulse Propagation");
+
+    type Input<'a> = Network<'a>;
+
+    fn parse(input: &str) -> Result<Self::Input<'_>> {
+        parse(input)
+    }
+
+    fn part1(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        let mut simulator = Simulator::new(input.clone(), TRIGGER)?;
+        let mut counts = [0usize; 2];
+        for _ in 0..1000 {
+            simulator.press(|pulse| counts[pulse.signal as usize] += 1);
+        }
+        Ok((counts[0] * counts[1]).into())
+    }
+
+    fn part2(input: &Self::Input<'_>, log: &Log) -> Result<Answer> {
+        // The network is made of independent components that each send a high
+        // pulse to the conjunction feeding rx every so many presses, so rx
+        // first gets a low pulse after the least common multiple of those.
+        let gcd = |mut a: usize, mut b: usize| {
+            while b != 0 {
+                (a, b) = (b, a % b);
+            }
+            a
+        };
+        let analysis = analyse(input, TRIGGER)?;
+        Ok(analysis
+            .components
+            .iter()
+            .map(|component| {
+                let length = cycle_length(input, component, analysis.sink)?;
+                log.note(format_args!(
+                    "{} -> {} repeats every {length} presses",
+                    component.start, component.terminal
+                ));
+                Ok(length)
+            })
+            .try_fold(1, |a, b| b.map(|b| a * b / gcd(a, b)))?
+            .into())
+    }
 }
 
 #[cfg(test)]
@@ -144,6 +278,106 @@ mod test {
 
     #[test]
     fn test_sample() {
-        assert_eq!(solve(SAMPLE), 32000000);
+        assert_eq!(Day20::solve(SAMPLE).unwrap(), 32000000);
+    }
+
+    // Two counters that reset after 3 and 5 presses.
+    const COUNTERS: &str = "
+    broadcaster -> ax, bx
+    %ax -> ay, ca
+    %ay -> ca
+    &ca -> ta, ax
+    &ta -> sink
+    %bx -> by, cb
+    %by -> bz
+    %bz -> cb
+    &cb -> tb, bx, by
+    &tb -> sink
+    &sink -> rx";
+
+    #[test]
+    fn test_components() {
+        let modules = parse(COUNTERS).unwrap();
+        let analysis = analyse(&modules, TRIGGER).unwrap();
+        assert_eq!(analysis.sink, "sink");
+        let components: Vec<_> = analysis
+            .components
+            .iter()
+            .map(|c| (c.start, c.terminal, c.modules.len()))
+            .collect();
+        assert_eq!(components, [("ax", "ta", 4), ("bx", "tb", 5)]);
+
+        // Compare with pressing the button until rx gets a low pulse.
+        let mut simulator = Simulator::new(modules, "broadcaster").unwrap();
+        let mut done = false;
+        while !done {
+            simulator.press(|pulse| done |= !pulse.signal && pulse.dest == "rx");
+        }
+        assert_eq!(simulator.presses(), 15);
+        assert_eq!(Day20::solve_2(COUNTERS).unwrap(), 15);
+    }
+
+    #[test]
+    fn test_long_cycle() {
+        // No flip-flops, but the counter's state takes 7 presses to come round.
+        const COUNTER: &str = "
+        broadcaster -> ax
+        #7ax -> ay
+        &ay -> ta
+        &ta -> sink
+        &sink -> rx";
+        assert_eq!(Day20::solve_2(COUNTER).unwrap(), 7);
+    }
+
+    #[test]
+    fn test_bad_structure() {
+        let error = |input| Day20::solve_2(input).unwrap_err().to_string();
+        assert!(error(SAMPLE).contains("expected one module to send to rx"));
+        let flip_flop = COUNTERS.replace("&sink", "%sink");
+        assert!(error(&flip_flop).contains("isn't a conjunction"));
+        let crossed = COUNTERS.replace("&tb -> sink", "&tb -> sink, ay");
+        assert!(error(&crossed).contains("through 2 modules"));
+        let shared = COUNTERS
+            .replace("&tb -> sink", "&tb -> sink, out")
+            .replace("%ay -> ca", "%ay -> ca, out");
+        assert!(error(&shared).contains("out is reached from both"));
+        let uneven = COUNTERS.replace("&ca -> ta, ax", "&ca -> ta");
+        assert!(error(&uneven).contains("every"));
+        // iv always sends high, so ta always sends low.
+        let silent = "broadcaster -> iv\n&iv -> ta\n&ta -> sink\n&sink -> rx";
+        assert!(error(silent).contains("ta never sends a high pulse to sink"));
+    }
+
+    #[test]
+    fn test_dot() {
+        let modules = parse(COUNTERS).unwrap();
+        let dot = to_dot(&modules, None, false);
+        assert!(dot.starts_with("digraph modules {\n    \"ax\" [shape=box, label=\"%ax\"];"));
+        assert!(dot.contains("    \"broadcaster\" [shape=diamond, label=\"broadcaster\"];"));
+        assert!(dot.contains("    \"rx\" [shape=plaintext, label=\"rx\"];"));
+        assert!(dot.contains("    \"sink\" -> \"rx\";"));
+
+        // Names that are DOT keywords still have to be nodes.
+        let keywords = parse("broadcaster -> node\n%node -> edge").unwrap();
+        let dot = to_dot(&keywords, None, false);
+        assert!(dot.contains("    \"node\" [shape=box, label=\"%node\"];"));
+        assert!(dot.contains("    \"node\" -> \"edge\";"));
+
+        let analysis = analyse(&modules, TRIGGER).unwrap();
+        let mut simulator = Simulator::new(modules, "broadcaster").unwrap();
+        simulator.press(|_| ());
+        let dot = to_dot(simulator.modules(), Some(&analysis), true);
+        assert!(dot.contains(
+            "\"ax\" [shape=box, label=\"%ax\\non\", style=filled, fillcolor=lightblue];"
+        ));
+        assert!(dot.contains(
+            "\"ca\" [shape=ellipse, label=\"&ca\\nax=high ay=low\", style=filled, fillcolor=lightblue];"
+        ));
+        assert!(dot.contains(
+            "\"bx\" [shape=box, label=\"%bx\\non\", style=filled, fillcolor=lightpink];"
+        ));
+        assert!(dot.contains(
+            "\"sink\" [shape=ellipse, label=\"&sink\\nta=low tb=low\", style=filled, fillcolor=gray];"
+        ));
     }
 }
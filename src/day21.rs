@@ -1,100 +1,293 @@
-use std::{
-    collections::{HashSet, VecDeque},
-    iter::zip,
+use std::{collections::VecDeque, iter::zip};
+
+use glam::IVec2;
+
+use crate::{
+    solution::{Answer, Error, Log, Result, Solution},
+    utils::{search::distances, Grid, ORTHOGONAL},
 };
 
-fn parse(input: &str) -> ((i64, i64), HashSet<(i64, i64)>) {
-    let mut gardens = HashSet::new();
-    let mut start = (0, 0);
-    for (y, line) in input.trim().lines().enumerate() {
-        for (x, c) in line.trim().chars().enumerate() {
-            if c == '.' {
-                gardens.insert((x as i64, y as i64));
-            }
-            if c == 'S' {
-                start = (x as i64, y as i64);
-            }
+/// Parses the map into the start and whether each tile is a garden plot.
+pub fn parse(input: &str) -> Result<(IVec2, Grid<bool>)> {
+    let map = Grid::parse(input, |c| "#.S".contains(c).then_some(c))?;
+    let start = map
+        .find(|&c| c == 'S')
+        .ok_or_else(|| Error::solve("no start tile"))?;
+    Ok((start, map.map(|&c| c != '#')))
+}
+
+/// Counts the plots reachable in exactly `max` steps on the infinitely tiled
+/// map.
+pub fn run(gardens: &Grid<bool>, start: IVec2, max: i64) -> i64 {
+    // Nothing further than `max` away as the crow flies can be reached in time.
+    let successors = |&p: &IVec2| {
+        ORTHOGONAL.into_iter().map(move |d| p + d).filter(|&q| {
+            let d = (q - start).abs();
+            *gardens.get_wrapping(q) && (d.x + d.y) as i64 <= max
+        })
+    };
+    distances([start], successors)
+        .into_values()
+        .filter(|&distance| distance as i64 <= max && distance as i64 % 2 == max % 2)
+        .count() as i64
+}
+
+/// How many plots to search at most before giving up on the distances
+/// settling.
+const MAX_SEARCH: usize = 1 << 22;
+
+/// Counts the plots reachable in exactly `steps` steps on the infinitely tiled
+/// map, whatever its shape, where the start is and however many steps.
+///
+/// The tiles near the start are searched directly, which is enough on its own
+/// if no walk that short can leave them. Otherwise the tiles beyond are counted
+/// from the outermost searched ones. Far enough out, each plot in a tile is
+/// always the same distance, the tile's period, further away than the same plot
+/// in the next tile in, and the outermost tiles must be seen doing that.
+pub fn count_reachable(gardens: &Grid<bool>, start: IVec2, steps: i64, log: &Log) -> Result<i64> {
+    let mut radius = 2;
+    while (2 * radius as usize + 3).pow(2) * gardens.width() * gardens.height() <= MAX_SEARCH {
+        let tiling = Tiling::search(gardens, start, radius);
+        if steps < tiling.exit {
+            log.note(format_args!(
+                "no walk leaves the {radius} tiles around the start"
+            ));
+            return Ok(tiling.count_inside(steps));
+        }
+        if let Some(count) = tiling.count(steps) {
+            log.note(format_args!("distances settled {radius} tiles out"));
+            return Ok(count);
         }
+        radius *= 2;
     }
-    gardens.insert(start);
-    (start, gardens)
+    Err(Error::solve(
+        "the distances to far tiles don't settle into a pattern",
+    ))
 }
 
-fn size(points: &HashSet<(i64, i64)>) -> i64 {
-    let Some((x, y)) = points
-        .iter()
-        .copied()
-        .reduce(|(x1, y1), (x2, y2)| (x1.max(x2), y1.max(y2)))
-    else {
-        return 0;
-    };
-    assert_eq!(x, y);
-    x + 1
+/// Distances from the start to every plot in the tiles up to one beyond
+/// `radius` from its own.
+struct Tiling {
+    size: IVec2,
+    radius: i32,
+    distances: Grid<Option<i64>>,
+    /// The distance to the nearest plot on the edge of the searched tiles. No
+    /// shorter walk can leave them, so the distances below it are exact.
+    exit: i64,
 }
 
-fn run(gardens: &HashSet<(i64, i64)>, start: (i64, i64), max: i64) -> i64 {
-    let mut seen = HashSet::new();
-    let mut queue: VecDeque<_> = [(0, start)].into();
-    let size = size(&gardens);
-    while let Some((distance, (x, y))) = queue.pop_front() {
-        if distance > max
-            || !gardens.contains(&(x.rem_euclid(size), y.rem_euclid(size)))
-            || !seen.insert((x, y))
-        {
-            continue;
+impl Tiling {
+    fn search(gardens: &Grid<bool>, start: IVec2, radius: i32) -> Self {
+        let size = gardens.size();
+        let tiles = 2 * radius + 3;
+        let mut distances =
+            Grid::filled((size.x * tiles) as usize, (size.y * tiles) as usize, None);
+        // The tiles start at a multiple of the size, so wrapping still lines
+        // up with the map.
+        let start = start + size * (radius + 1);
+        *distances.get_mut(start).unwrap() = Some(0);
+        let mut queue = VecDeque::from([(start, 0)]);
+        let mut exit = i64::MAX;
+        let edge = distances.size() - 1;
+        while let Some((p, distance)) = queue.pop_front() {
+            if p.x == 0 || p.y == 0 || p.x == edge.x || p.y == edge.y {
+                exit = exit.min(distance);
+            }
+            for q in ORTHOGONAL.map(|d| p + d) {
+                if *gardens.get_wrapping(q) {
+                    if let Some(cell @ None) = distances.get_mut(q) {
+                        *cell = Some(distance + 1);
+                        queue.push_back((q, distance + 1));
+                    }
+                }
+            }
         }
-        queue.extend(
-            [(-1, 0), (0, -1), (1, 0), (0, 1)]
-                .into_iter()
-                .map(|(dx, dy)| (distance + 1, (x + dx, y + dy))),
+        Tiling {
+            size,
+            radius,
+            distances,
+            exit,
+        }
+    }
+
+    fn tile(&self, tile: IVec2) -> Vec<Option<i64>> {
+        let corner = (tile + self.radius + 1) * self.size;
+        (0..self.size.y)
+            .flat_map(|y| (0..self.size.x).map(move |x| IVec2::new(x, y)))
+            .map(|p| self.distances.get(corner + p).copied().flatten())
+            .collect()
+    }
+
+    /// How much further away every plot in `tile` is than in the tile one
+    /// `step` back towards the start, if that's the same for all of them and
+    /// the same again between that tile and the next one in.
+    fn period(&self, tile: IVec2, step: IVec2) -> Option<i64> {
+        let rows = zip(
+            self.tile(tile),
+            zip(self.tile(tile - step), self.tile(tile - step * 2)),
         );
+        let mut period = None;
+        for row in rows {
+            match row {
+                (None, (None, None)) => {}
+                (Some(outer), (Some(middle), Some(inner)))
+                    if outer - middle == middle - inner
+                        && outer > middle
+                        && period.unwrap_or(outer - middle) == outer - middle =>
+                {
+                    period = Some(outer - middle);
+                }
+                _ => return None,
+            }
+        }
+        // Nothing in the tile can be reached, so any period will do.
+        Some(period.unwrap_or(1))
+    }
+
+    /// Counts the reachable plots in the searched tiles alone.
+    fn count_inside(&self, steps: i64) -> i64 {
+        self.distances
+            .values()
+            .flatten()
+            .filter(|&&d| d <= steps && (steps - d) % 2 == 0)
+            .count() as i64
+    }
+
+    /// Counts the reachable plots, or `None` if the outermost tiles don't yet
+    /// follow the pattern.
+    fn count(&self, steps: i64) -> Option<i64> {
+        let r = self.radius;
+        let mut total = 0;
+        for y in -r..=r {
+            for x in -r..=r {
+                let tile = IVec2::new(x, y);
+                let outward = IVec2::new(
+                    if x.abs() == r { x.signum() } else { 0 },
+                    if y.abs() == r { y.signum() } else { 0 },
+                );
+                let across = match outward.x {
+                    0 => None,
+                    _ => Some(self.period(tile, outward * IVec2::X)?),
+                };
+                let down = match outward.y {
+                    0 => None,
+                    _ => Some(self.period(tile, outward * IVec2::Y)?),
+                };
+                // Going out diagonally must cost the same as going across and
+                // then down, or the quadrant isn't that simple yet.
+                if let (Some(across), Some(down)) = (across, down) {
+                    if self.period(tile, outward)? != across + down {
+                        return None;
+                    }
+                }
+                // Tiles further out along `outward` are all counted from this
+                // one, as a line of tiles or a whole quadrant.
+                let count = |distance: i64| {
+                    let left = steps - distance;
+                    match (across, down) {
+                        (None, None) => (left >= 0 && left % 2 == 0) as i64,
+                        (Some(period), None) | (None, Some(period)) => line(left, period),
+                        (Some(across), Some(down)) => plane(left, across, down),
+                    }
+                };
+                total += self
+                    .tile(tile)
+                    .into_iter()
+                    .flatten()
+                    .map(count)
+                    .sum::<i64>();
+            }
+        }
+        Some(total)
     }
-    seen.into_iter()
-        .filter(|(x, y)| (x + y - start.0 - start.1).rem_euclid(2) == max % 2)
-        .count() as i64
 }
 
-pub fn solve(input: &str) -> i64 {
-    let (start, gardens) = parse(input);
-    run(&gardens, start, 64)
+/// The number of `k >= 0` with `k * period` at most `left` and of the same
+/// parity.
+fn line(left: i64, period: i64) -> i64 {
+    if left < 0 {
+        return 0;
+    }
+    let n = left / period + 1;
+    match (period % 2 == 0, left % 2 == 0) {
+        (true, true) => n,
+        (true, false) => 0,
+        (false, true) => (n + 1) / 2,
+        (false, false) => n / 2,
+    }
 }
 
-fn interpolate(points: &[(i64, i64)], estimate: i64) -> i64 {
-    let mut numerators: Vec<_> = points.iter().map(|&(_, y)| y as i128).collect();
-    let mut denoms: Vec<_> = points.iter().map(|_| 1).collect();
-    for i in 0..points.len() {
-        for j in 0..points.len() {
-            if i != j {
-                numerators[i] *= estimate as i128 - points[j].0 as i128;
-                denoms[i] *= points[i].0 as i128 - points[j].0 as i128;
+/// The number of `i, j >= 0` with `i * across + j * down` at most `left` and
+/// of the same parity.
+fn plane(left: i64, across: i64, down: i64) -> i64 {
+    // Fixing the parities of `i` and `j` fixes the parity of the sum, so each
+    // matching pair of parities is a plain count with doubled periods.
+    let mut total = 0;
+    for p in 0..2 {
+        for q in 0..2 {
+            let rest = left - p * across - q * down;
+            if rest % 2 == 0 {
+                total += below(rest, 2 * across, 2 * down);
             }
         }
     }
-    let lcm = denoms.iter().fold(1, |a: i128, b: &i128| {
-        let (mut a, mut b) = (a.abs(), b.abs());
-        let prod = a * b;
-        while b != 0 {
-            (a, b) = (b, a % b);
+    total
+}
+
+/// The number of `i, j >= 0` with `i * across + j * down` at most `left`.
+fn below(left: i64, across: i64, down: i64) -> i64 {
+    if left < 0 {
+        return 0;
+    }
+    // Summed over `i` from the outermost column in, that's the number of `j`
+    // up to `(left % across + k * across) / down` for `k` from 0 to `n - 1`.
+    let n = (left / across + 1) as i128;
+    (n + floor_sum(n, down as i128, across as i128, (left % across) as i128)) as i64
+}
+
+/// The sum of `(a * k + b) / m` for `k` from 0 to `n - 1`, with `a` and `b`
+/// not negative, in logarithmic time.
+fn floor_sum(mut n: i128, mut m: i128, mut a: i128, mut b: i128) -> i128 {
+    let mut total = 0;
+    loop {
+        if a >= m {
+            total += n * (n - 1) / 2 * (a / m);
+            a %= m;
+        }
+        if b >= m {
+            total += n * (b / m);
+            b %= m;
         }
-        prod / a
-    });
-    (zip(numerators, denoms)
-        .map(|(num, denom)| num * lcm / denom)
-        .sum::<i128>()
-        / lcm) as i64
+        let last = a * n + b;
+        if last < m {
+            return total;
+        }
+        (n, b) = (last / m, last % m);
+        (m, a) = (a, m);
+    }
 }
 
-pub fn solve_2(input: &str) -> i64 {
-    let (start, gardens) = parse(input);
-    let size = size(&gardens);
-    let mut points = Vec::new();
-    for i in 0..3 {
-        let x = size / 2 + i * size;
-        let y = run(&gardens, start, x);
-        points.push((x, y));
-    }
-    println!("{points:?}");
-    interpolate(&points, 26501365)
+pub struct Day21;
+
+impl Solution for Day21 {
+    const DAY: u32 = 21;
+    const TITLE: Option<&'static str> = Some("Step Counter");
+
+    type Input<'a> = (IVec2, Grid<bool>);
+
+    fn parse(input: &str) -> Result<Self::Input<'_>> {
+        parse(input)
+    }
+
+    fn part1(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        let (start, gardens) = input;
+        Ok(run(gardens, *start, 64).into())
+    }
+
+    fn part2(input: &Self::Input<'_>, log: &Log) -> Result<Answer> {
+        let &(start, ref gardens) = input;
+        Ok(count_reachable(gardens, start, 26501365, log)?.into())
+    }
 }
 
 #[cfg(test)]
@@ -116,13 +309,13 @@ mod test {
 
     #[test]
     fn test_sample() {
-        let (start, gardens) = parse(SAMPLE);
+        let (start, gardens) = parse(SAMPLE).unwrap();
         assert_eq!(run(&gardens, start, 6), 16);
     }
 
     #[test]
     fn test_sample_2() {
-        let (start, gardens) = parse(SAMPLE);
+        let (start, gardens) = parse(SAMPLE).unwrap();
         assert_eq!(run(&gardens, start, 10), 50);
         assert_eq!(run(&gardens, start, 50), 1594);
         assert_eq!(run(&gardens, start, 100), 6536);
@@ -132,17 +325,82 @@ mod test {
     }
 
     #[test]
-    fn test_interpolation() {
-        let values = [
-            (65, 3720),
-            (196, 33150),
-            (327, 91890),
-            (26501365, 599763113936220),
-        ];
-        for i in 0..values.len() {
-            let mut values = Vec::from(values.clone());
-            let (x, y) = values.remove(i);
-            assert_eq!(interpolate(&values, x), y);
+    fn test_count_reachable() {
+        let (start, gardens) = parse(SAMPLE).unwrap();
+        for steps in 0..60 {
+            let expected = run(&gardens, start, steps);
+            assert_eq!(
+                count_reachable(&gardens, start, steps, &Log::quiet()),
+                Ok(expected)
+            );
+        }
+        assert_eq!(
+            count_reachable(&gardens, start, 500, &Log::quiet()),
+            Ok(167004)
+        );
+        assert_eq!(
+            count_reachable(&gardens, start, 5000, &Log::quiet()),
+            Ok(16733044)
+        );
+    }
+
+    #[test]
+    fn test_rectangular() {
+        const MAP: &str = "
+        .......
+        .#..#..
+        ...#...
+        .S.....
+        ";
+        let (start, gardens) = parse(MAP).unwrap();
+        for steps in 0..80 {
+            let expected = run(&gardens, start, steps);
+            assert_eq!(
+                count_reachable(&gardens, start, steps, &Log::quiet()),
+                Ok(expected)
+            );
+        }
+    }
+
+    #[test]
+    fn test_winding() {
+        // No row is clear, and getting across means winding around the walls.
+        const MAP: &str = "
+        ..#...
+        .S#.#.
+        ..#.#.
+        ....#.
+        ";
+        let (start, gardens) = parse(MAP).unwrap();
+        for steps in (0..80).chain([151, 302]) {
+            let expected = run(&gardens, start, steps);
+            assert_eq!(
+                count_reachable(&gardens, start, steps, &Log::quiet()),
+                Ok(expected)
+            );
+        }
+    }
+
+    #[test]
+    fn test_far() {
+        // The old solver sampled three step counts and fitted a quadratic,
+        // which is right for a square map like this one.
+        const OPEN: &str = "
+        .....
+        .#.#.
+        ..S..
+        .#.#.
+        .....
+        ";
+        let (start, gardens) = parse(OPEN).unwrap();
+        for (steps, expected) in [
+            (26501365, 589950803166748),
+            (1_000_000_007, 840000012800000048),
+        ] {
+            assert_eq!(
+                count_reachable(&gardens, start, steps, &Log::quiet()),
+                Ok(expected)
+            );
         }
     }
 }
@@ -5,23 +5,28 @@ use itertools::Itertools;
 use nom::{
     bytes::complete::tag,
     character::complete::{digit1, multispace0},
-    combinator::{map, map_res},
+    combinator::{map, map_opt, map_res},
     multi::{many1, separated_list1},
     sequence::{pair, preceded},
     IResult,
 };
 
-struct Brick {
+use crate::solution::{parse_all, Answer, Log, Result, Solution};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+pub struct Brick {
     p1: IVec3,
     p2: IVec3,
 }
 
 impl Brick {
-    fn parse(input: &str) -> IResult<&str, Self> {
+    pub fn parse(input: &str) -> IResult<&str, Self> {
         fn point(input: &str) -> IResult<&str, IVec3> {
-            map(
+            map_opt(
                 separated_list1(tag(","), map_res(digit1, str::parse)),
-                |coords| IVec3::from_slice(&coords),
+                |coords: Vec<i32>| (coords.len() == 3).then(|| IVec3::from_slice(&coords)),
             )(input)
         }
         map(pair(point, preceded(tag("~"), point)), |(p1, p2)| Brick {
@@ -30,7 +35,7 @@ impl Brick {
         })(input)
     }
 
-    fn footprint(&self) -> impl Iterator<Item = IVec2> {
+    pub fn footprint(&self) -> impl Iterator<Item = IVec2> {
         let min = self.p1.xy().min(self.p2.xy());
         let max = self.p1.xy().max(self.p2.xy());
         (min.x..=max.x)
@@ -38,20 +43,20 @@ impl Brick {
             .map(IVec2::from)
     }
 
-    fn z(&self) -> i32 {
+    pub fn z(&self) -> i32 {
         self.p1.min(self.p2).z
     }
 
-    fn height(&self) -> i32 {
+    pub fn height(&self) -> i32 {
         self.p1.max(self.p2).z - self.z() + 1
     }
 }
 
-fn parse(input: &str) -> IResult<&str, Vec<Brick>> {
-    many1(preceded(multispace0, Brick::parse))(input)
+pub fn parse(input: &str) -> Result<Vec<Brick>> {
+    parse_all(input, many1(preceded(multispace0, Brick::parse)))
 }
 
-fn num_dependents(bricks: &[Brick]) -> Vec<usize> {
+pub fn num_dependents(bricks: &[Brick]) -> Vec<usize> {
     // For each brick, find its new z value, the bricks it's resting on below,
     // and the bricks that are resting on it above.
     let mut below = vec![HashSet::<usize>::new(); bricks.len()];
@@ -69,14 +74,18 @@ fn num_dependents(bricks: &[Brick]) -> Vec<usize> {
             }
         }
         z[i] = *layers.keys().max().unwrap_or(&1);
-        for j in layers.remove(&z[i]).unwrap_or_else(HashSet::new) {
+        for j in layers.remove(&z[i]).unwrap_or_default() {
             below[i].insert(j);
             above[j].insert(i);
         }
     }
     // Now for each brick, figure out which ones will fall when it's
     // disintegrated.
-    (0..bricks.len())
+    #[cfg(feature = "parallel")]
+    let starts = (0..bricks.len()).into_par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let starts = 0..bricks.len();
+    starts
         .map(|start_i| {
             let mut seen = HashSet::new();
             let mut falling: HashSet<usize> = [start_i].into();
@@ -95,17 +104,29 @@ fn num_dependents(bricks: &[Brick]) -> Vec<usize> {
         .collect()
 }
 
-pub fn solve(input: &str) -> usize {
-    let bricks = parse(input).unwrap().1;
-    num_dependents(&bricks)
-        .into_iter()
-        .filter(|&n| n == 0)
-        .count()
-}
+pub struct Day22;
+
+impl Solution for Day22 {
+    const DAY: u32 = 22;
+    const TITLE: Option<&'static str> = Some("Sand Slabs");
+
+    type Input<'a> = Vec<Brick>;
 
-pub fn solve_2(input: &str) -> usize {
-    let bricks = parse(input).unwrap().1;
-    num_dependents(&bricks).into_iter().sum()
+    fn parse(input: &str) -> Result<Self::Input<'_>> {
+        parse(input)
+    }
+
+    fn part1(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        Ok(num_dependents(input)
+            .into_iter()
+            .filter(|&n| n == 0)
+            .count()
+            .into())
+    }
+
+    fn part2(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        Ok(num_dependents(input).into_iter().sum::<usize>().into())
+    }
 }
 
 #[cfg(test)]
@@ -123,11 +144,11 @@ mod test {
 
     #[test]
     fn test_sample() {
-        assert_eq!(solve(SAMPLE), 5);
+        assert_eq!(Day22::solve(SAMPLE).unwrap(), 5);
     }
 
     #[test]
     fn test_sample_2() {
-        assert_eq!(solve_2(SAMPLE), 7);
+        assert_eq!(Day22::solve_2(SAMPLE).unwrap(), 7);
     }
 }
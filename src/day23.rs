@@ -1,107 +1,121 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{HashMap, HashSet};
 
 use glam::IVec2;
 
+use crate::{
+    solution::{Answer, Error, Log, Result, Solution},
+    utils::{search::distances, Grid},
+};
+
 const DIRS: &[IVec2] = &[IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y];
 
-fn parse(input: &str) -> HashMap<IVec2, &'static [IVec2]> {
-    let mut result = HashMap::new();
-    for (y, line) in input.trim().lines().enumerate() {
-        for (x, c) in line.trim().chars().enumerate() {
-            let kind = match c {
-                '.' => DIRS,
-                '>' => &DIRS[0..1],
-                '<' => &DIRS[1..2],
-                'v' => &DIRS[2..3],
-                '^' => &DIRS[3..4],
-                '#' => continue,
-                _ => panic!(),
-            };
-            result.insert(IVec2::new(x as i32, y as i32), kind);
-        }
-    }
-    result
+/// The directions that can be taken from each tile, or `None` for forest.
+pub type Trails = Grid<Option<&'static [IVec2]>>;
+
+pub fn parse(input: &str) -> Result<Trails> {
+    Grid::parse(input, |c| match c {
+        '.' => Some(Some(DIRS)),
+        '>' => Some(Some(&DIRS[0..1])),
+        '<' => Some(Some(&DIRS[1..2])),
+        'v' => Some(Some(&DIRS[2..3])),
+        '^' => Some(Some(&DIRS[3..4])),
+        '#' => Some(None),
+        _ => None,
+    })
 }
 
-fn adjacent<'a>(
-    trails: &'a HashMap<IVec2, &'static [IVec2]>,
-    p: IVec2,
-) -> impl Iterator<Item = IVec2> + 'a {
-    trails
-        .get(&p)
-        .unwrap_or(&&DIRS[0..0])
-        .into_iter()
+pub fn adjacent(trails: &Trails, p: IVec2) -> impl Iterator<Item = IVec2> + '_ {
+    trails[p]
+        .unwrap_or_default()
+        .iter()
         .map(move |&d| p + d)
-        .filter(|p| trails.contains_key(p))
+        .filter(|&q| matches!(trails.get(q), Some(Some(_))))
 }
 
-fn run(trails: HashMap<IVec2, &'static [IVec2]>) -> usize {
-    let &start_pos = trails.keys().min_by_key(|p| p.y).unwrap();
-    let &end_pos = trails.keys().max_by_key(|p| p.y).unwrap();
+pub fn run(trails: &Trails) -> Result<usize> {
+    let mut open = trails.iter().filter(|(_, t)| t.is_some()).map(|(p, _)| p);
+    let start_pos = open
+        .next()
+        .ok_or_else(|| Error::solve("there are no trails"))?;
+    let end_pos = open.last().unwrap_or(start_pos);
     // List of start and end, and all intersections (points with 3 options).
     let mut intersections: HashSet<_> = [start_pos, end_pos].into();
     intersections.extend(
         trails
-            .keys()
-            .copied()
-            .filter(|&p| adjacent(&trails, p).count() >= 3),
+            .iter()
+            .filter(|(_, t)| t.is_some())
+            .map(|(p, _)| p)
+            .filter(|&p| adjacent(trails, p).count() >= 3),
     );
-    // Perform a BFS from each intersection to find the linear routes to the
+    // Search out from each intersection to find the linear routes to the
     // neighbouring intersections
     let mut neighbours: HashMap<IVec2, Vec<(usize, IVec2)>> = HashMap::new();
     for &start in &intersections {
-        let mut seen = HashSet::new();
-        let mut queue: VecDeque<_> = [(0, start)].into();
-        while let Some((distance, p)) = queue.pop_front() {
-            if !trails.contains_key(&p) || !seen.insert(p) {
-                continue;
-            }
-            if p != start && intersections.contains(&p) {
-                neighbours.entry(start).or_default().push((distance, p));
-                continue;
-            }
-            queue.extend(adjacent(&trails, p).map(|p| (distance + 1, p)));
-        }
+        let reachable = distances([start], |&p| {
+            let stop = p != start && intersections.contains(&p);
+            adjacent(trails, p).filter(move |_| !stop)
+        });
+        neighbours.insert(
+            start,
+            reachable
+                .into_iter()
+                .filter(|(p, _)| *p != start && intersections.contains(p))
+                .map(|(p, distance)| (distance, p))
+                .collect(),
+        );
     }
     // Perform a DFS over the intersections to find the longest path
-    enum DFS {
+    enum Dfs {
         Search(usize, IVec2),
         Backtrack(IVec2),
     }
-    let mut stack: Vec<_> = [DFS::Search(0, start_pos)].into();
+    let mut stack: Vec<_> = [Dfs::Search(0, start_pos)].into();
     let mut seen = HashSet::new();
     let mut max_distance = 0;
     while let Some(dfs) = stack.pop() {
         match dfs {
-            DFS::Search(distance, p) => {
+            Dfs::Search(distance, p) => {
                 if !seen.insert(p) {
                     continue;
                 }
-                stack.push(DFS::Backtrack(p));
+                stack.push(Dfs::Backtrack(p));
                 if p == end_pos {
                     max_distance = max_distance.max(distance);
                     continue;
                 }
                 if let Some(e) = neighbours.get(&p) {
                     for &(delta, p) in e {
-                        stack.push(DFS::Search(distance + delta, p));
+                        stack.push(Dfs::Search(distance + delta, p));
                     }
                 }
             }
-            DFS::Backtrack(p) => {
+            Dfs::Backtrack(p) => {
                 seen.remove(&p);
             }
         }
     }
-    max_distance
+    Ok(max_distance)
 }
 
-pub fn solve(input: &str) -> usize {
-    run(parse(input))
-}
+pub struct Day23;
 
-pub fn solve_2(input: &str) -> usize {
-    run(parse(input).into_keys().map(|p| (p, DIRS)).collect())
+impl Solution for Day23 {
+    const DAY: u32 = 23;
+    const TITLE: Option<&'static str> = Some("A Long Walk");
+
+    type Input<'a> = Trails;
+
+    fn parse(input: &str) -> Result<Self::Input<'_>> {
+        parse(input)
+    }
+
+    fn part1(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        Ok(run(input)?.into())
+    }
+
+    fn part2(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        Ok(run(&input.map(|t| t.map(|_| DIRS)))?.into())
+    }
 }
 
 #[cfg(test)]
@@ -136,11 +150,11 @@ mod test {
 
     #[test]
     fn test_sample() {
-        assert_eq!(solve(SAMPLE), 94);
+        assert_eq!(Day23::solve(SAMPLE).unwrap(), 94);
     }
 
     #[test]
     fn test_sample_2() {
-        assert_eq!(solve_2(SAMPLE), 154);
+        assert_eq!(Day23::solve_2(SAMPLE).unwrap(), 154);
     }
 }
@@ -1,71 +1,73 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 
-struct Schematic {
+use glam::IVec2;
+
+use crate::{
+    solution::{Answer, Log, Result, Solution},
+    utils::Grid,
+};
+
+pub struct Schematic {
+    grid: Grid<char>,
     numbers: Vec<i64>,
-    number_indices: HashMap<(i64, i64), usize>,
-    symbols: HashMap<(i64, i64), char>,
+    number_at: Grid<Option<usize>>,
+}
+
+fn is_symbol(c: char) -> bool {
+    c != '.' && !c.is_ascii_digit()
 }
 
 impl Schematic {
-    fn new(input: &str) -> Schematic {
+    pub fn new(input: &str) -> Result<Schematic> {
+        let grid = Grid::parse(input, Some)?;
         let mut numbers = Vec::new();
-        let mut number_indices = HashMap::new();
-        let mut symbols = HashMap::new();
-        for (y, line) in input.trim().lines().enumerate() {
+        let mut number_at = grid.map(|_| None);
+        for (y, row) in grid.rows().enumerate() {
             let mut partial_number = None;
-            for (x, c) in line.trim().chars().chain(['.']).enumerate() {
+            for (x, &c) in row.iter().chain(&['.']).enumerate() {
                 if let Some(digit) = c.to_digit(10) {
                     let number = partial_number.unwrap_or(0);
                     partial_number = Some(number * 10 + digit as i64);
-                    number_indices.insert((x as i64, y as i64), numbers.len());
-                    continue;
-                }
-                if let Some(number) = partial_number.take() {
+                    number_at[IVec2::new(x as i32, y as i32)] = Some(numbers.len());
+                } else if let Some(number) = partial_number.take() {
                     numbers.push(number);
                 }
-                if c != '.' {
-                    symbols.insert((x as i64, y as i64), c);
-                }
             }
         }
-        Schematic {
+        Ok(Schematic {
+            grid,
             numbers,
-            number_indices,
-            symbols,
-        }
+            number_at,
+        })
     }
 
-    fn part_numbers(&self) -> impl Iterator<Item = i64> + '_ {
+    pub fn part_numbers(&self) -> impl Iterator<Item = i64> + '_ {
         let mut seen = HashSet::new();
-        self.number_indices.iter().filter_map(move |(&(x, y), &i)| {
+        self.number_at.iter().filter_map(move |(p, &i)| {
+            let i = i?;
             if seen.contains(&i) {
                 return None;
             }
-            for dy in [-1, 0, 1] {
-                for dx in [-1, 0, 1] {
-                    if self.symbols.contains_key(&(x + dx, y + dy)) {
-                        seen.insert(i);
-                        return Some(self.numbers[i]);
-                    }
-                }
+            if self.grid.neighbours8(p).any(|q| is_symbol(self.grid[q])) {
+                seen.insert(i);
+                return Some(self.numbers[i]);
             }
             None
         })
     }
 
-    fn gear_ratios(&self) -> impl Iterator<Item = i64> + '_ {
-        self.symbols
+    pub fn gear_ratios(&self) -> impl Iterator<Item = i64> + '_ {
+        self.grid
             .iter()
             .filter(|(_, &c)| c == '*')
-            .filter_map(|(&(x, y), _)| {
-                let mut adjacent = HashSet::new();
-                for dy in [-1, 0, 1] {
-                    for dx in [-1, 0, 1] {
-                        adjacent.extend(self.number_indices.get(&(x + dx, y + dy)));
-                    }
-                }
+            .filter_map(|(p, _)| {
+                let adjacent: HashSet<_> = self
+                    .grid
+                    .neighbours8(p)
+                    .filter_map(|q| self.number_at[q])
+                    .collect();
                 if adjacent.len() == 2 {
-                    Some(adjacent.into_iter().map(|&i| self.numbers[i]).product())
+                    Some(adjacent.into_iter().map(|i| self.numbers[i]).product())
                 } else {
                     None
                 }
@@ -73,12 +75,25 @@ impl Schematic {
     }
 }
 
-pub fn solve(input: &str) -> i64 {
-    Schematic::new(input).part_numbers().sum()
-}
+pub struct Day3;
+
+impl Solution for Day3 {
+    const DAY: u32 = 3;
+    const TITLE: Option<&'static str> = Some("Gear Ratios");
 
-pub fn solve_2(input: &str) -> i64 {
-    Schematic::new(input).gear_ratios().sum()
+    type Input<'a> = Schematic;
+
+    fn parse(input: &str) -> Result<Self::Input<'_>> {
+        Schematic::new(input)
+    }
+
+    fn part1(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        Ok(input.part_numbers().sum::<i64>().into())
+    }
+
+    fn part2(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        Ok(input.gear_ratios().sum::<i64>().into())
+    }
 }
 
 #[cfg(test)]
@@ -99,11 +114,11 @@ mod test {
 
     #[test]
     fn test_sample() {
-        assert_eq!(solve(SAMPLE), 4361);
+        assert_eq!(Day3::solve(SAMPLE).unwrap(), 4361);
     }
 
     #[test]
     fn test_sample_2() {
-        assert_eq!(solve_2(SAMPLE), 467835);
+        assert_eq!(Day3::solve_2(SAMPLE).unwrap(), 467835);
     }
 }
@@ -6,65 +6,82 @@ use std::{
 use nom::{
     bytes::complete::tag,
     character::complete::{digit1, multispace0, space0, space1},
-    combinator::{map, map_res},
-    multi::{fold_many0, many1},
+    combinator::{map, map_opt, map_res},
+    multi::{many0, many1},
     sequence::{pair, preceded, tuple},
     IResult,
 };
 
-fn n_winners(input: &str) -> impl Iterator<Item = usize> {
+use crate::solution::{parse_all, Answer, Log, Result, Solution};
+
+pub fn n_winners(input: &str) -> Result<Vec<usize>> {
+    // Each list of numbers must not repeat itself.
     fn numbers(input: &str) -> IResult<&str, HashSet<usize>> {
-        fold_many0(
-            preceded(space0, map_res(digit1, str::parse)),
-            HashSet::new,
-            |mut acc, n| {
-                assert!(acc.insert(n));
-                acc
+        map_opt(
+            many0(preceded(space0, map_res(digit1, str::parse))),
+            |numbers: Vec<usize>| {
+                let set: HashSet<_> = numbers.iter().copied().collect();
+                (set.len() == numbers.len()).then_some(set)
             },
         )(input)
     }
-    many1(preceded(
-        multispace0,
-        preceded(
-            tuple((tag("Card"), space1, digit1, tag(":"))),
-            map(
-                pair(numbers, preceded(pair(space0, tag("|")), numbers)),
-                |(a, b)| a.intersection(&b).count(),
+    parse_all(
+        input,
+        many1(preceded(
+            multispace0,
+            preceded(
+                tuple((tag("Card"), space1, digit1, tag(":"))),
+                map(
+                    pair(numbers, preceded(pair(space0, tag("|")), numbers)),
+                    |(a, b)| a.intersection(&b).count(),
+                ),
             ),
-        ),
-    ))(input)
-    .unwrap()
-    .1
-    .into_iter()
+        )),
+    )
 }
 
-pub fn solve(input: &str) -> usize {
-    n_winners(input)
-        .map(|n| match n {
-            0 => 0,
-            i => 2usize.pow(i as u32 - 1),
-        })
-        .sum()
-}
+pub struct Day4;
+
+impl Solution for Day4 {
+    const DAY: u32 = 4;
+    const TITLE: Option<&'static str> = Some("Scratchcards");
+
+    type Input<'a> = Vec<usize>;
+
+    fn parse(input: &str) -> Result<Self::Input<'_>> {
+        n_winners(input)
+    }
+
+    fn part1(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        Ok(input
+            .iter()
+            .map(|&n| match n {
+                0 => 0,
+                i => 2usize.pow(i as u32 - 1),
+            })
+            .sum::<usize>()
+            .into())
+    }
 
-pub fn solve_2(input: &str) -> usize {
-    let mut queue = BinaryHeap::new();
-    let mut running_copies = 0;
-    let mut total_cards = 0;
-    for (i, n) in n_winners(input).enumerate() {
-        let count = 1 + running_copies;
-        total_cards += count;
-        running_copies += count;
-        queue.push(Reverse((i + n, count)));
-        while let Some(&Reverse((i2, count))) = queue.peek() {
-            if i < i2 {
-                break;
+    fn part2(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        let mut queue = BinaryHeap::new();
+        let mut running_copies = 0;
+        let mut total_cards: usize = 0;
+        for (i, &n) in input.iter().enumerate() {
+            let count = 1 + running_copies;
+            total_cards += count;
+            running_copies += count;
+            queue.push(Reverse((i + n, count)));
+            while let Some(&Reverse((i2, count))) = queue.peek() {
+                if i < i2 {
+                    break;
+                }
+                running_copies -= count;
+                queue.pop();
             }
-            running_copies -= count;
-            queue.pop();
         }
+        Ok(total_cards.into())
     }
-    total_cards
 }
 
 #[cfg(test)]
@@ -81,11 +98,11 @@ mod test {
 
     #[test]
     fn test_sample() {
-        assert_eq!(solve(SAMPLE), 13);
+        assert_eq!(Day4::solve(SAMPLE).unwrap(), 13);
     }
 
     #[test]
     fn test_sample_2() {
-        assert_eq!(solve_2(SAMPLE), 30);
+        assert_eq!(Day4::solve_2(SAMPLE).unwrap(), 30);
     }
 }
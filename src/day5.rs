@@ -1,88 +1,207 @@
-use std::mem::swap;
+use std::{fmt::Display, ops::Range};
 
 use itertools::Itertools;
 use nom::{
     bytes::complete::tag,
     character::complete::{alpha1, digit1, multispace0, space0},
-    combinator::{map, map_res},
+    combinator::map_res,
     multi::many1,
     sequence::{pair, preceded, tuple},
     IResult,
 };
 
-fn evaluator<'a>(
-    layers: impl Iterator<Item = &'a Vec<(usize, usize, usize)>> + Clone,
-    forwards: bool,
-) -> impl Fn(usize) -> usize {
-    move |mut value| {
-        for layer in layers.clone() {
-            for &(mut dest, mut src, len) in layer {
-                if !forwards {
-                    swap(&mut dest, &mut src);
-                }
-                if (src..src + len).contains(&value) {
-                    value = dest + value - src;
-                    break;
+use crate::{
+    solution::{parse_all, Answer, Error, Log, Result, Solution},
+    utils::intervals::IntervalSet,
+};
+
+pub type Layer = Vec<(usize, usize, usize)>;
+
+/// A function that adds an offset to the values in each of a set of ranges,
+/// and leaves every other value alone.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Mapping {
+    /// Sorted, disjoint source ranges with a non-zero offset, where adjacent
+    /// ranges have different offsets.
+    pieces: Vec<(Range<i64>, i64)>,
+}
+
+impl Mapping {
+    pub fn identity() -> Self {
+        Mapping::default()
+    }
+
+    /// Returns `None` if any of the source ranges overlap.
+    fn from_pieces(pieces: impl IntoIterator<Item = (Range<i64>, i64)>) -> Option<Self> {
+        let mut sorted: Vec<_> = pieces
+            .into_iter()
+            .filter(|(r, offset)| !r.is_empty() && *offset != 0)
+            .collect();
+        sorted.sort_by_key(|(r, _)| r.start);
+        let mut pieces: Vec<(Range<i64>, i64)> = Vec::new();
+        for (r, offset) in sorted {
+            match pieces.last_mut() {
+                Some((last, _)) if r.start < last.end => return None,
+                Some((last, last_offset)) if r.start == last.end && offset == *last_offset => {
+                    last.end = r.end;
                 }
+                _ => pieces.push((r, offset)),
             }
         }
-        value
+        Some(Mapping { pieces })
+    }
+
+    pub fn from_layer(layer: &Layer) -> Result<Self> {
+        Mapping::from_pieces(layer.iter().map(|&(dest, src, len)| {
+            let (dest, src, len) = (dest as i64, src as i64, len as i64);
+            (src..src + len, dest - src)
+        }))
+        .ok_or_else(|| Error::solve("a map has overlapping source ranges"))
     }
-}
 
-fn mapping(layers: Vec<Vec<(usize, usize, usize)>>) -> impl Fn(usize, usize) -> usize {
-    let candidates: Vec<_> = layers
-        .iter()
-        .enumerate()
-        .flat_map(|(i, layer)| layer.iter().map(move |l| (i, l)))
-        .flat_map(|(i, &(_, src, len))| {
-            let invert = evaluator(layers[..i].iter().rev(), false);
-            [invert(src), invert(src + len)]
-        })
-        .collect();
-    move |start, len| {
-        candidates
+    /// Composes the layers into a single mapping from seeds to locations.
+    pub fn from_layers(layers: &[Layer]) -> Result<Self> {
+        layers
             .iter()
-            .filter(|i| (start..start + len).contains(i))
-            .copied()
-            .chain([start])
-            .map(evaluator(layers.iter(), true))
-            .min()
-            .unwrap()
+            .try_fold(Mapping::identity(), |mapping, layer| {
+                Ok(mapping.then(&Mapping::from_layer(layer)?))
+            })
+    }
+
+    /// The pieces with the gaps between them filled in, covering every value.
+    fn covering(&self) -> Vec<(Range<i64>, i64)> {
+        let mut start = i64::MIN;
+        let mut covering = Vec::new();
+        for (r, offset) in &self.pieces {
+            covering.push((start..r.start, 0));
+            covering.push((r.clone(), *offset));
+            start = r.end;
+        }
+        covering.push((start..i64::MAX, 0));
+        covering.retain(|(r, _)| !r.is_empty());
+        covering
+    }
+
+    /// The mapping that applies this one and then `next`.
+    pub fn then(&self, next: &Mapping) -> Mapping {
+        let next = next.covering();
+        let mut pieces = Vec::new();
+        for (r, offset) in self.covering() {
+            let image = r.start + offset..r.end + offset;
+            let first = next.partition_point(|(n, _)| n.end <= image.start);
+            for (n, next_offset) in next[first..]
+                .iter()
+                .take_while(|(n, _)| n.start < image.end)
+            {
+                let overlap = image.start.max(n.start)..image.end.min(n.end);
+                pieces.push((
+                    overlap.start - offset..overlap.end - offset,
+                    offset + next_offset,
+                ));
+            }
+        }
+        Mapping::from_pieces(pieces).expect("the pieces of a composed mapping are disjoint")
+    }
+
+    pub fn get(&self, value: i64) -> i64 {
+        let i = self.pieces.partition_point(|(r, _)| r.end <= value);
+        match self.pieces.get(i) {
+            Some((r, offset)) if r.contains(&value) => value + offset,
+            _ => value,
+        }
+    }
+
+    /// Every value that some value in `values` maps to.
+    pub fn image(&self, values: &IntervalSet) -> IntervalSet {
+        values.map_piecewise(&self.pieces)
+    }
+
+    /// The mapping that undoes this one, or `None` if two values map to the
+    /// same place.
+    pub fn inverse(&self) -> Option<Mapping> {
+        let mut images: Vec<_> = self
+            .covering()
+            .into_iter()
+            .map(|(r, offset)| (r.start + offset..r.end + offset, -offset))
+            .collect();
+        images.sort_by_key(|(r, _)| r.start);
+        // Each value is hit once exactly when the images tile the values with
+        // no overlaps.
+        let tiles = images.windows(2).all(|w| w[0].0.end == w[1].0.start);
+        tiles.then(|| Mapping::from_pieces(images)).flatten()
+    }
+
+    /// The pieces as `(source, destination, length)`, in order of source.
+    pub fn segments(&self) -> impl Iterator<Item = (i64, i64, i64)> + '_ {
+        self.pieces
+            .iter()
+            .map(|(r, offset)| (r.start, r.start + offset, r.end - r.start))
     }
 }
 
-fn parse(input: &str) -> (Vec<usize>, impl Fn(usize, usize) -> usize) {
+impl Display for Mapping {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (src, dest, len)) in self.segments().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{src}..{} -> {dest}..{}", src + len, dest + len)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn parse(input: &str) -> Result<(Vec<usize>, Vec<Layer>)> {
     fn number(input: &str) -> IResult<&str, usize> {
         preceded(space0, map_res(digit1, str::parse))(input)
     }
-    pair(
-        preceded(tuple((multispace0, tag("seeds:"))), many1(number)),
-        map(
+    parse_all(
+        input,
+        pair(
+            preceded(tuple((multispace0, tag("seeds:"))), many1(number)),
             many1(preceded(
                 tuple((multispace0, alpha1, tag("-to-"), alpha1, tag(" map:"))),
                 many1(preceded(multispace0, tuple((number, number, number)))),
             )),
-            mapping,
         ),
-    )(input)
-    .unwrap()
-    .1
+    )
 }
 
-pub fn solve(input: &str) -> usize {
-    let (numbers, f) = parse(input);
-    numbers.into_iter().map(|n| f(n, 1)).min().unwrap()
-}
+pub struct Day5;
+
+impl Solution for Day5 {
+    const DAY: u32 = 5;
+    const TITLE: Option<&'static str> = Some("If You Give A Seed A Fertilizer");
+
+    type Input<'a> = (Vec<usize>, Vec<Layer>);
+
+    fn parse(input: &str) -> Result<Self::Input<'_>> {
+        parse(input)
+    }
 
-pub fn solve_2(input: &str) -> usize {
-    let (numbers, f) = parse(input);
-    numbers
-        .into_iter()
-        .tuples()
-        .map(|(start, len)| f(start, len))
-        .min()
-        .unwrap()
+    fn part1(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        let (seeds, layers) = input;
+        let seeds = seeds.iter().map(|&n| n as i64..n as i64 + 1).collect();
+        Mapping::from_layers(layers)?
+            .image(&seeds)
+            .min()
+            .map(Answer::from)
+            .ok_or_else(|| Error::solve("no seeds"))
+    }
+
+    fn part2(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        let (seeds, layers) = input;
+        let seeds = seeds
+            .iter()
+            .tuples()
+            .map(|(&start, &len)| start as i64..(start + len) as i64)
+            .collect();
+        Mapping::from_layers(layers)?
+            .image(&seeds)
+            .min()
+            .map(Answer::from)
+            .ok_or_else(|| Error::solve("no seed ranges"))
+    }
 }
 
 #[cfg(test)]
@@ -126,11 +245,36 @@ mod test {
 
     #[test]
     fn test_sample() {
-        assert_eq!(solve(SAMPLE), 35);
+        assert_eq!(Day5::solve(SAMPLE).unwrap(), 35);
     }
 
     #[test]
     fn test_sample_2() {
-        assert_eq!(solve_2(SAMPLE), 46);
+        assert_eq!(Day5::solve_2(SAMPLE).unwrap(), 46);
+    }
+
+    #[test]
+    fn test_mapping() {
+        let (_, layers) = parse(SAMPLE).unwrap();
+        let mapping = Mapping::from_layers(&layers).unwrap();
+        let locations: Vec<_> = [79, 14, 55, 13].map(|seed| mapping.get(seed)).into();
+        assert_eq!(locations, [82, 43, 86, 35]);
+        assert!(mapping.segments().is_sorted_by_key(|(src, _, _)| src));
+        assert_eq!(mapping.get(1000), 1000);
+
+        let inverse = mapping.inverse().unwrap();
+        assert_eq!(inverse.get(46), 82);
+        assert_eq!(inverse.then(&mapping), Mapping::identity());
+
+        let image = mapping.image(&(82..83).into());
+        assert_eq!(image.min(), Some(46));
+        assert_eq!(image.len(), 1);
+        assert_eq!(mapping.image(&(79..93).into()).len(), 14);
+
+        let overlapping = Mapping::from_layer(&vec![(0, 10, 5), (20, 12, 5)]);
+        assert!(overlapping.is_err());
+        let squashed = Mapping::from_layer(&vec![(0, 10, 5)]).unwrap();
+        assert_eq!(squashed.inverse(), None);
+        assert_eq!(squashed.to_string(), "10..15 -> 0..5");
     }
 }
@@ -10,36 +10,59 @@ use nom::{
     IResult,
 };
 
-fn parse(input: &str) -> (impl Iterator<Item = usize>, impl Iterator<Item = usize>) {
+use crate::solution::{parse_all, Answer, Error, Log, Result, Solution};
+
+pub fn parse(input: &str) -> Result<(Vec<usize>, Vec<usize>)> {
     fn number(input: &str) -> IResult<&str, usize> {
         preceded(space0, map_res(digit1, str::parse))(input)
     }
-    let (_, (times, distances)) = pair(
-        preceded(tuple((multispace0, tag("Time:"), space0)), many1(number)),
-        preceded(
-            tuple((multispace0, tag("Distance:"), space0)),
-            many1(number),
+    parse_all(
+        input,
+        pair(
+            preceded(tuple((multispace0, tag("Time:"), space0)), many1(number)),
+            preceded(
+                tuple((multispace0, tag("Distance:"), space0)),
+                many1(number),
+            ),
         ),
-    )(input)
-    .unwrap();
-    (times.into_iter(), distances.into_iter())
+    )
 }
 
-fn n_wins(time: usize, distance: usize) -> usize {
+pub fn n_wins(time: usize, distance: usize) -> usize {
     (1..time).filter(|t| (time - t) * t > distance).count()
 }
 
-pub fn solve(input: &str) -> usize {
-    let (times, distances) = parse(input);
-    zip(times, distances).map(|(t, d)| n_wins(t, d)).product()
-}
+pub struct Day6;
 
-pub fn solve_2(input: &str) -> usize {
-    let (mut times, mut distances) = parse(input);
-    n_wins(
-        times.join("").parse().unwrap(),
-        distances.join("").parse().unwrap(),
-    )
+impl Solution for Day6 {
+    const DAY: u32 = 6;
+    const TITLE: Option<&'static str> = Some("Wait For It");
+
+    type Input<'a> = (Vec<usize>, Vec<usize>);
+
+    fn parse(input: &str) -> Result<Self::Input<'_>> {
+        parse(input)
+    }
+
+    fn part1(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        let (times, distances) = input;
+        Ok(zip(times, distances)
+            .map(|(&t, &d)| n_wins(t, d))
+            .product::<usize>()
+            .into())
+    }
+
+    fn part2(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        let (times, distances) = input;
+        let join = |numbers: &[usize]| {
+            numbers
+                .iter()
+                .join("")
+                .parse()
+                .map_err(|_| Error::solve("joined number is too large"))
+        };
+        Ok(n_wins(join(times)?, join(distances)?).into())
+    }
 }
 
 #[cfg(test)]
@@ -52,11 +75,11 @@ mod test {
 
     #[test]
     fn test_sample() {
-        assert_eq!(solve(SAMPLE), 288);
+        assert_eq!(Day6::solve(SAMPLE).unwrap(), 288);
     }
 
     #[test]
     fn test_sample_2() {
-        assert_eq!(solve_2(SAMPLE), 71503);
+        assert_eq!(Day6::solve_2(SAMPLE).unwrap(), 71503);
     }
 }
@@ -3,14 +3,16 @@ use std::collections::HashMap;
 use itertools::Itertools;
 use nom::{
     character::complete::{alphanumeric1, digit1, multispace0, space0},
-    combinator::map_res,
+    combinator::{map_res, verify},
     multi::many1,
     sequence::{pair, preceded},
     IResult,
 };
 
+use crate::solution::{parse_all, Answer, Log, Result, Solution};
+
 #[derive(PartialEq, Eq, PartialOrd, Ord)]
-enum HandType {
+pub enum HandType {
     HighCard,
     Pair,
     TwoPair,
@@ -20,7 +22,7 @@ enum HandType {
     FiveOfAKind,
 }
 
-fn sort_key(joker: Option<char>) -> impl Fn(&str) -> (HandType, Vec<usize>) {
+pub fn sort_key(joker: Option<char>) -> impl Fn(&str) -> (HandType, Vec<usize>) {
     let mut all_cards = vec![
         'A', 'K', 'Q', 'J', 'T', '9', '8', '7', '6', '5', '4', '3', '2',
     ];
@@ -59,32 +61,53 @@ fn sort_key(joker: Option<char>) -> impl Fn(&str) -> (HandType, Vec<usize>) {
     }
 }
 
-fn run<F, K>(input: &str, key: F) -> usize
+pub fn parse(input: &str) -> Result<Vec<(&str, usize)>> {
+    fn hand(input: &str) -> IResult<&str, &str> {
+        verify(alphanumeric1, |hand: &str| {
+            hand.len() == 5 && hand.chars().all(|c| "AKQJT98765432".contains(c))
+        })(input)
+    }
+    parse_all(
+        input,
+        many1(preceded(
+            multispace0,
+            pair(hand, preceded(space0, map_res(digit1, str::parse))),
+        )),
+    )
+}
+
+pub fn run<F, K>(hands: &[(&str, usize)], key: F) -> usize
 where
     F: Fn(&str) -> K,
     K: Ord,
 {
-    fn parse(input: &str) -> IResult<&str, Vec<(&str, usize)>> {
-        many1(preceded(
-            multispace0,
-            pair(alphanumeric1, preceded(space0, map_res(digit1, str::parse))),
-        ))(input)
-    }
-    let (_, hands) = parse(input).unwrap();
     hands
-        .into_iter()
-        .map(|(hand, bid)| (key(hand), bid))
+        .iter()
+        .map(|&(hand, bid)| (key(hand), bid))
         .sorted()
         .enumerate()
         .fold(0, |acc, (i, (_, bid))| acc + (i + 1) * bid)
 }
 
-pub fn solve(input: &str) -> usize {
-    run(input, sort_key(None))
-}
+pub struct Day7;
+
+impl Solution for Day7 {
+    const DAY: u32 = 7;
+    const TITLE: Option<&'static str> = Some("Camel Cards");
+
+    type Input<'a> = Vec<(&'a str, usize)>;
+
+    fn parse(input: &str) -> Result<Self::Input<'_>> {
+        parse(input)
+    }
+
+    fn part1(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        Ok(run(input, sort_key(None)).into())
+    }
 
-pub fn solve_2(input: &str) -> usize {
-    run(input, sort_key(Some('J')))
+    fn part2(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        Ok(run(input, sort_key(Some('J'))).into())
+    }
 }
 
 #[cfg(test)]
@@ -100,11 +123,11 @@ mod test {
 
     #[test]
     fn test_sample() {
-        assert_eq!(solve(SAMPLE), 6440);
+        assert_eq!(Day7::solve(SAMPLE).unwrap(), 6440);
     }
 
     #[test]
     fn test_sample_2() {
-        assert_eq!(solve_2(SAMPLE), 5905);
+        assert_eq!(Day7::solve_2(SAMPLE).unwrap(), 5905);
     }
 }
@@ -1,73 +1,94 @@
 use std::collections::HashMap;
 
 use nom::{
-    bytes::complete::tag,
-    character::complete::{alpha1, alphanumeric1, multispace0},
+    bytes::complete::{is_a, tag},
+    character::complete::{alphanumeric1, multispace0},
     combinator::map,
     multi::many1,
     sequence::{pair, preceded, terminated},
-    IResult,
 };
 
-fn parse<'a>(input: &str) -> IResult<&str, (&str, HashMap<&str, (&str, &str)>)> {
-    pair(
-        preceded(multispace0, alpha1),
-        map(
-            many1(preceded(
-                multispace0,
-                pair(
-                    terminated(alphanumeric1, tag(" = (")),
+use crate::solution::{parse_all, Answer, Error, Log, Result, Solution};
+
+pub type Edges<'a> = HashMap<&'a str, (&'a str, &'a str)>;
+
+pub fn parse(input: &str) -> Result<(&str, Edges<'_>)> {
+    parse_all(
+        input,
+        pair(
+            preceded(multispace0, is_a("LR")),
+            map(
+                many1(preceded(
+                    multispace0,
                     pair(
-                        terminated(alphanumeric1, tag(", ")),
-                        terminated(alphanumeric1, tag(")")),
+                        terminated(alphanumeric1, tag(" = (")),
+                        pair(
+                            terminated(alphanumeric1, tag(", ")),
+                            terminated(alphanumeric1, tag(")")),
+                        ),
                     ),
-                ),
-            )),
-            |v| v.into_iter().collect(),
+                )),
+                |v| v.into_iter().collect(),
+            ),
         ),
-    )(input)
+    )
 }
 
-fn run<'a, 'b>(
+pub fn run<'a, 'b>(
     instructions: &'a str,
-    edges: &'b HashMap<&'a str, (&'a str, &'a str)>,
+    edges: &'b Edges<'a>,
     terminate: impl Fn(&'a str) -> bool,
-    mut pos: &'a str,
-) -> usize {
-    for (step, instruction) in instructions.chars().cycle().enumerate() {
+    start: &'a str,
+) -> Result<usize> {
+    // By then some node has been reached at the same point in the
+    // instructions twice, so the walk only goes round in circles.
+    let limit = instructions.len() * edges.len();
+    let mut pos = start;
+    for (step, instruction) in instructions.chars().cycle().take(limit + 1).enumerate() {
         if terminate(pos) {
-            return step;
-        }
-        let &(l, r) = edges.get(pos).unwrap();
-        match instruction {
-            'L' => pos = l,
-            'R' => pos = r,
-            _ => panic!(),
+            return Ok(step);
         }
+        let &(l, r) = edges
+            .get(pos)
+            .ok_or_else(|| Error::solve(format!("no node named {pos}")))?;
+        pos = if instruction == 'L' { l } else { r };
     }
-    unreachable!()
-}
-
-pub fn solve(input: &str) -> usize {
-    let (_, (instructions, edges)) = parse(input).unwrap();
-    run(instructions, &edges, |p| p == "ZZZ", "AAA")
+    Err(Error::solve(format!("{start} never reaches the end")))
 }
 
-fn gcd(mut a: usize, mut b: usize) -> usize {
+pub fn gcd(mut a: usize, mut b: usize) -> usize {
     while b != 0 {
         (a, b) = (b, a % b);
     }
     a
 }
 
-pub fn solve_2(input: &str) -> usize {
-    let (_, (instructions, edges)) = parse(input).unwrap();
-    edges
-        .keys()
-        .filter(|k| k.ends_with('A'))
-        .map(|k| run(instructions, &edges, |p| p.ends_with('Z'), k))
-        .reduce(|a, b| a * b / gcd(a, b))
-        .unwrap()
+pub struct Day8;
+
+impl Solution for Day8 {
+    const DAY: u32 = 8;
+    const TITLE: Option<&'static str> = Some("Haunted Wasteland");
+
+    type Input<'a> = (&'a str, Edges<'a>);
+
+    fn parse(input: &str) -> Result<Self::Input<'_>> {
+        parse(input)
+    }
+
+    fn part1(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        let (instructions, edges) = input;
+        Ok(run(instructions, edges, |p| p == "ZZZ", "AAA")?.into())
+    }
+
+    fn part2(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        let (instructions, edges) = input;
+        Ok(edges
+            .keys()
+            .filter(|k| k.ends_with('A'))
+            .map(|k| run(instructions, edges, |p| p.ends_with('Z'), k))
+            .try_fold(1, |a, b| b.map(|b| a * b / gcd(a, b)))?
+            .into())
+    }
 }
 
 #[cfg(test)]
@@ -77,7 +98,7 @@ mod test {
     #[test]
     fn test_sample() {
         assert_eq!(
-            solve(
+            Day8::solve(
                 "
                 RL
     
@@ -88,22 +109,34 @@ mod test {
                 EEE = (EEE, EEE)
                 GGG = (GGG, GGG)
                 ZZZ = (ZZZ, ZZZ)"
-            ),
+            )
+            .unwrap(),
             2
         );
     }
 
+    #[test]
+    fn test_unreachable() {
+        assert_eq!(
+            Day8::solve("RL\n\nAAA = (AAA, AAA)")
+                .unwrap_err()
+                .to_string(),
+            "AAA never reaches the end"
+        );
+    }
+
     #[test]
     fn test_sample_2() {
         assert_eq!(
-            solve(
+            Day8::solve(
                 "
                 LLR
 
                 AAA = (BBB, BBB)
                 BBB = (AAA, ZZZ)
                 ZZZ = (ZZZ, ZZZ)"
-            ),
+            )
+            .unwrap(),
             6
         );
     }
@@ -111,7 +144,7 @@ mod test {
     #[test]
     fn test_sample_part_2() {
         assert_eq!(
-            solve_2(
+            Day8::solve_2(
                 "
                 LR
 
@@ -123,7 +156,8 @@ mod test {
                 22C = (22Z, 22Z)
                 22Z = (22B, 22B)
                 XXX = (XXX, XXX)"
-            ),
+            )
+            .unwrap(),
             6
         );
     }
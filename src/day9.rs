@@ -1,4 +1,3 @@
-use itertools::Itertools;
 use nom::{
     bytes::complete::tag,
     character::complete::{digit1, multispace0, space0},
@@ -8,40 +7,103 @@ use nom::{
     IResult,
 };
 
-fn parse(input: &str) -> Vec<Vec<i64>> {
+use crate::{
+    solution::{parse_all, Answer, Error, Log, Result, Solution},
+    utils::poly::{self, differences, Newton},
+};
+
+pub fn parse(input: &str) -> Result<Vec<Vec<i64>>> {
     fn number(input: &str) -> IResult<&str, i64> {
         map_res(recognize(pair(opt(tag("-")), digit1)), str::parse)(input)
     }
-    many1(preceded(multispace0, many1(preceded(space0, number))))(input)
-        .unwrap()
-        .1
+    parse_all(
+        input,
+        many1(preceded(multispace0, many1(preceded(space0, number)))),
+    )
+}
+
+/// A sequence whose differences, taken enough times, are all zero, so its
+/// values follow a polynomial.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sequence {
+    table: Vec<Vec<i64>>,
+    degree: usize,
+    newton: Newton,
 }
 
-fn run(nums: Vec<i64>) -> i64 {
-    if nums.iter().all(|&n| n == 0) {
-        return 0;
+impl Sequence {
+    pub fn new(values: &[i64]) -> Result<Self> {
+        if values.is_empty() {
+            return Err(Error::solve("the sequence is empty"));
+        }
+        let degree = poly::degree(values)
+            .ok_or_else(|| Error::solve("the differences never reach all zeros"))?;
+        Ok(Sequence {
+            table: differences(values),
+            degree,
+            newton: Newton::new(values),
+        })
+    }
+
+    /// The values, then each row of differences of the one above, ending with
+    /// the row of zeros.
+    pub fn table(&self) -> &[Vec<i64>] {
+        &self.table
+    }
+
+    pub fn degree(&self) -> usize {
+        self.degree
+    }
+
+    /// The value `k` places after the last one.
+    pub fn next(&self, k: usize) -> Result<i128> {
+        let last = self.table[0].len() - 1;
+        let x = last.checked_add(k).and_then(|x| i64::try_from(x).ok());
+        x.and_then(|x| self.newton.at(x))
+            .ok_or_else(|| Error::solve(format!("the value {k} after the last overflows")))
+    }
+
+    /// The value `k` places before the first one.
+    pub fn previous(&self, k: usize) -> Result<i128> {
+        let x = i64::try_from(k).ok();
+        x.and_then(|x| self.newton.at(-x))
+            .ok_or_else(|| Error::solve(format!("the value {k} before the first overflows")))
     }
-    nums[nums.len() - 1]
-        + run(nums
-            .into_iter()
-            .tuple_windows()
-            .map(|(a, b)| b - a)
-            .collect())
 }
 
-pub fn solve(input: &str) -> i64 {
-    parse(input).into_iter().map(run).sum()
+/// Sums what `predict` says about each sequence.
+pub fn run(input: &[Vec<i64>], predict: impl Fn(&Sequence) -> Result<i128>) -> Result<i64> {
+    let mut total: i128 = 0;
+    for (i, values) in input.iter().enumerate() {
+        let prediction = Sequence::new(values)
+            .and_then(|sequence| predict(&sequence))
+            .map_err(|err| Error::solve(format!("sequence {}: {err}", i + 1)))?;
+        total = total
+            .checked_add(prediction)
+            .ok_or_else(|| Error::solve("the sum is too large"))?;
+    }
+    i64::try_from(total).map_err(|_| Error::solve("the sum is too large"))
 }
 
-pub fn solve_2(input: &str) -> i64 {
-    parse(input)
-        .into_iter()
-        .map(|mut seq| {
-            seq.reverse();
-            seq
-        })
-        .map(run)
-        .sum()
+pub struct Day9;
+
+impl Solution for Day9 {
+    const DAY: u32 = 9;
+    const TITLE: Option<&'static str> = Some("Mirage Maintenance");
+
+    type Input<'a> = Vec<Vec<i64>>;
+
+    fn parse(input: &str) -> Result<Self::Input<'_>> {
+        parse(input)
+    }
+
+    fn part1(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        Ok(run(input, |sequence| sequence.next(1))?.into())
+    }
+
+    fn part2(input: &Self::Input<'_>, _log: &Log) -> Result<Answer> {
+        Ok(run(input, |sequence| sequence.previous(1))?.into())
+    }
 }
 
 #[cfg(test)]
@@ -55,30 +117,73 @@ mod test {
 
     #[test]
     fn test_sample_negative() {
-        // 0   1  -1   C
-        //   1  -2   B
-        //    -3   A
-        //       0
-        assert_eq!(solve("0 1 -1"), -6)
+        // 0   1  -1  -6   D
+        //   1  -2  -5   C
+        //    -3  -3   B
+        //       0   A
+        assert_eq!(Day9::solve("0 1 -1 -6").unwrap(), -14)
+    }
+
+    #[test]
+    fn test_not_polynomial() {
+        // The differences run out before they reach a row of zeros.
+        assert_eq!(
+            Day9::solve("0 3 6\n0 1 -1").unwrap_err().to_string(),
+            "sequence 2: the differences never reach all zeros"
+        );
+        assert!(Sequence::new(&[1, 2, 4, 8, 16]).is_err());
+        assert!(Sequence::new(&[]).is_err());
+    }
+
+    #[test]
+    fn test_sequence() {
+        let sequence = Sequence::new(&[10, 13, 16, 21, 30, 45]).unwrap();
+        assert_eq!(
+            sequence.table(),
+            [
+                vec![10, 13, 16, 21, 30, 45],
+                vec![3, 3, 5, 9, 15],
+                vec![0, 2, 4, 6],
+                vec![2, 2, 2],
+                vec![0, 0],
+            ]
+        );
+        assert_eq!(sequence.degree(), 3);
+        assert_eq!((sequence.next(1), sequence.previous(1)), (Ok(68), Ok(5)));
+        // Far ahead, the closed form agrees with extending the table a value
+        // at a time.
+        let mut rows = sequence.table().to_vec();
+        for k in 1..=50 {
+            for i in (0..rows.len() - 1).rev() {
+                let next = rows[i].last().unwrap() + rows[i + 1].last().unwrap();
+                rows[i].push(next);
+            }
+            assert_eq!(sequence.next(k), Ok(*rows[0].last().unwrap() as i128));
+        }
+        assert_eq!(sequence.previous(3), Ok(-19));
+        // A cubic that far out doesn't fit in an i128.
+        assert!(sequence.next(10_000_000_000_000).is_err());
+        assert!(sequence.next(usize::MAX).is_err());
+        assert!(sequence.previous(usize::MAX).is_err());
     }
 
     #[test]
     fn test_sample_small() {
-        assert_eq!(solve("0 3 6 9 12 15"), 18)
+        assert_eq!(Day9::solve("0 3 6 9 12 15").unwrap(), 18)
     }
 
     #[test]
     fn test_sample_small_2() {
-        assert_eq!(solve_2("10 13 16 21 30 45"), 5)
+        assert_eq!(Day9::solve_2("10 13 16 21 30 45").unwrap(), 5)
     }
 
     #[test]
     fn test_sample() {
-        assert_eq!(solve(SAMPLE), 114)
+        assert_eq!(Day9::solve(SAMPLE).unwrap(), 114)
     }
 
     #[test]
     fn test_sample_2() {
-        assert_eq!(solve_2(SAMPLE), 2)
+        assert_eq!(Day9::solve_2(SAMPLE).unwrap(), 2)
     }
 }
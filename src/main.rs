@@ -1,40 +1,281 @@
-use clap::Parser;
+use std::{iter::zip, process::exit};
 
-mod utils;
+use adventofcode::{
+    day17::{self, Rules},
+    day19::{self, DecisionTree},
+    day20, runner,
+    utils::logic::{self, Simulator},
+    DAYS,
+};
+use clap::{Parser, Subcommand};
+use glam::IVec2;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
 struct Args {
-    #[arg(value_enum, default_value_t=Task::Latest)]
-    task: Task,
-}
-
-utils::make_runner!(
-    1+,
-    2+,
-    3+,
-    4+,
-    5+,
-    6+,
-    7+,
-    8+,
-    9+,
-    10+,
-    11+,
-    12+,
-    13+,
-    14+,
-    15+,
-    16+,
-    17+,
-    18+,
-    19+,
-    20+,
-    21+,
-    22+,
-    23+,
-);
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// "latest", "all", a single part ("day5", "day5-2") or a range of days
+    /// ("5", "5..12", "5..=12")
+    #[arg(default_value_t = runner::Selection::Latest)]
+    task: runner::Selection,
+
+    /// Read the puzzle input from this file ("-" for stdin) instead of the
+    /// embedded one
+    #[arg(short, long, global = true)]
+    input: Option<String>,
+
+    /// Time each solver over N runs (after a warmup) and report statistics
+    #[arg(long, value_name = "N")]
+    bench: Option<usize>,
+
+    /// Solve up to N parts at once, though their times then include waiting
+    /// for the CPU
+    #[arg(short, long, value_name = "N", default_value_t = 1)]
+    jobs: usize,
+
+    /// How to print the results
+    #[arg(long, value_enum, default_value_t = runner::Format::Text)]
+    format: runner::Format,
+
+    /// List the registered days instead of running them
+    #[arg(long)]
+    list: bool,
+
+    /// Print the solvers' intermediate values to stderr
+    #[arg(short, long)]
+    verbose: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Draw the cheapest day 17 routes, for both kinds of crucible unless
+    /// --min or --max is given
+    Crucible(CrucibleArgs),
+    /// List the boxes of day 19 parts that the workflows accept
+    Workflows {
+        /// Print the workflows as a Graphviz graph instead
+        #[arg(long)]
+        dot: bool,
+    },
+    /// Simulate the day 20 module network
+    Pulses(PulsesArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct CrucibleArgs {
+    /// Blocks the crucible must move in a straight line before turning
+    /// (defaults to 0)
+    #[arg(long, value_name = "N")]
+    min: Option<usize>,
+
+    /// Blocks the crucible can move in a straight line before turning
+    /// (defaults to 3, or --min if that's more)
+    #[arg(long, value_name = "N")]
+    max: Option<usize>,
+
+    /// Allow turning straight back
+    #[arg(long)]
+    reverse: bool,
+
+    /// Allow moving diagonally
+    #[arg(long)]
+    diagonal: bool,
+
+    /// Start here instead of the top left corner
+    #[arg(long, value_name = "X,Y", value_parser = parse_position)]
+    start: Option<IVec2>,
+
+    /// End here instead of the bottom right corner
+    #[arg(long, value_name = "X,Y", value_parser = parse_position)]
+    end: Option<IVec2>,
+
+    /// Lose N more heat for each block moved in a direction ("up", "down-left",
+    /// ...). Can be repeated
+    #[arg(long, value_name = "DIRECTION=N", value_parser = parse_cost)]
+    cost: Vec<(usize, usize)>,
+}
+
+#[derive(clap::Args, Debug)]
+struct PulsesArgs {
+    /// The module that the button sends to
+    #[arg(long, value_name = "NAME", default_value = day20::TRIGGER)]
+    trigger: String,
+
+    /// Press the button N times (once by default)
+    #[arg(long, value_name = "N")]
+    presses: Option<usize>,
+
+    /// Print every pulse
+    #[arg(long)]
+    trace: bool,
+
+    /// Print the state of the modules after each press
+    #[arg(long)]
+    states: bool,
+
+    /// Check the network against a trace printed by --trace instead
+    #[arg(long, value_name = "FILE")]
+    replay: Option<String>,
+
+    /// Print the network as a Graphviz graph instead, with the module states
+    /// after --presses if given
+    #[arg(long)]
+    dot: bool,
+
+    /// Colour each component of the graph
+    #[arg(long, requires = "dot")]
+    colour: bool,
+}
+
+fn parse_position(s: &str) -> Result<IVec2, String> {
+    let invalid = || format!("Invalid position {s:?}, expected X,Y");
+    let (x, y) = s.split_once(',').ok_or_else(invalid)?;
+    let coordinate = |n: &str| n.trim().parse().map_err(|_| invalid());
+    Ok(IVec2::new(coordinate(x)?, coordinate(y)?))
+}
+
+/// Parses a direction cost into an index into `ALL_DIRECTIONS` and the cost.
+fn parse_cost(s: &str) -> Result<(usize, usize), String> {
+    const NAMES: [&str; 8] = [
+        "up",
+        "right",
+        "down",
+        "left",
+        "up-right",
+        "down-right",
+        "down-left",
+        "up-left",
+    ];
+    let (name, cost) = s
+        .split_once('=')
+        .ok_or_else(|| format!("Invalid cost {s:?}, expected DIRECTION=N"))?;
+    let direction = NAMES
+        .iter()
+        .position(|&n| n == name)
+        .ok_or_else(|| format!("Invalid direction {name:?}, expected one of {NAMES:?}"))?;
+    let cost = cost
+        .parse()
+        .map_err(|_| format!("Invalid cost {cost:?} for {name}"))?;
+    Ok((direction, cost))
+}
+
+fn crucible(path: Option<&str>, args: &CrucibleArgs) -> Result<(), String> {
+    let input = runner::load_input(path, "17")?;
+    let grid = day17::parse(&input).map_err(|err| err.to_string())?;
+    let presets = match (args.min, args.max) {
+        (None, None) => vec![
+            ("Crucible", Rules::CRUCIBLE),
+            ("Ultra crucible", Rules::ULTRA),
+        ],
+        (min, max) => {
+            let min = min.unwrap_or(0);
+            let max = max.unwrap_or(min.max(3));
+            if min > max {
+                return Err(format!("--min {min} is more than --max {max}"));
+            }
+            vec![("Crucible", Rules::new(min, max))]
+        }
+    };
+    for (name, mut rules) in presets {
+        rules.reverse = args.reverse;
+        rules.diagonal = args.diagonal;
+        rules.start = args.start;
+        rules.end = args.end;
+        for &(direction, cost) in &args.cost {
+            rules.direction_costs[direction] = cost;
+        }
+        let route = day17::find_route(&grid, &rules).map_err(|err| format!("{name}: {err}"))?;
+        println!("{name} (heat loss {}):", route.heat_loss);
+        println!("{}\n", route.render(&grid));
+    }
+    Ok(())
+}
+
+fn workflows(path: Option<&str>, dot: bool) -> Result<(), String> {
+    let input = runner::load_input(path, "19")?;
+    let system = day19::parse(&input).map_err(|err| err.to_string())?;
+    let tree = DecisionTree::compile(&system).map_err(|err| err.to_string())?;
+    if dot {
+        print!("{}", tree.to_dot());
+        return Ok(());
+    }
+    let accepted = tree.accepted(tree.all_parts());
+    for parts in &accepted {
+        let ratings: Vec<_> = zip(&tree.categories, parts)
+            .map(|(category, values)| format!("{category}={values}"))
+            .collect();
+        println!("{}: {}", ratings.join(" "), day19::volume(parts));
+    }
+    let total: usize = accepted.iter().map(day19::volume).sum();
+    println!("{} boxes, {total} parts accepted", accepted.len());
+    Ok(())
+}
+
+fn pulses(path: Option<&str>, args: &PulsesArgs) -> Result<(), String> {
+    let input = runner::load_input(path, "20")?;
+    let modules = day20::parse(&input).map_err(|err| err.to_string())?;
+    let mut simulator = Simulator::new(modules, &args.trigger).map_err(|err| err.to_string())?;
+    if let Some(replay) = &args.replay {
+        let text = std::fs::read_to_string(replay)
+            .map_err(|err| format!("Failed to read trace from {replay}: {err}"))?;
+        let trace = logic::parse_trace(&text).map_err(|err| err.to_string())?;
+        simulator.replay(&trace).map_err(|err| err.to_string())?;
+        println!("{} pulses match", trace.len());
+        return Ok(());
+    }
+    for _ in 0..args.presses.unwrap_or(if args.dot { 0 } else { 1 }) {
+        simulator.press(|pulse| {
+            if args.trace {
+                println!("{pulse}");
+            }
+        });
+        if args.states {
+            println!("After press {}:\n{simulator}", simulator.presses());
+        }
+    }
+    if args.dot {
+        let analysis = args
+            .colour
+            .then(|| day20::analyse(simulator.modules(), &args.trigger))
+            .transpose()
+            .map_err(|err| err.to_string())?;
+        let states = args.presses.is_some();
+        print!(
+            "{}",
+            day20::to_dot(simulator.modules(), analysis.as_ref(), states)
+        );
+    }
+    Ok(())
+}
 
 fn main() {
-    run(Args::parse());
+    let args = Args::parse();
+    if let Some(command) = &args.command {
+        let result = match command {
+            Command::Crucible(crucible_args) => crucible(args.input.as_deref(), crucible_args),
+            &Command::Workflows { dot } => workflows(args.input.as_deref(), dot),
+            Command::Pulses(pulses_args) => pulses(args.input.as_deref(), pulses_args),
+        };
+        if let Err(err) = result {
+            eprintln!("{err}");
+            exit(1);
+        }
+        return;
+    }
+    if args.list {
+        runner::list(DAYS, args.format);
+        return;
+    }
+    runner::run(
+        DAYS,
+        &args.task,
+        args.input.as_deref(),
+        args.bench,
+        args.jobs,
+        args.format,
+        args.verbose,
+    );
 }
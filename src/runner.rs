@@ -0,0 +1,591 @@
+use std::{
+    fmt::Display,
+    io::Read,
+    iter::zip,
+    process::exit,
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::Instant,
+};
+
+use itertools::Itertools;
+
+use crate::solution::{Error, Log, Registered};
+
+include!(concat!(env!("OUT_DIR"), "/embedded.rs"));
+
+/// Reads the puzzle input for `day` from `path`, or stdin if `path` is "-".
+/// Falls back to the input embedded from `inputs/<day>.txt` at build time.
+pub fn load_input(path: Option<&str>, day: &str) -> Result<String, String> {
+    match path {
+        Some("-") => {
+            let mut input = String::new();
+            std::io::stdin()
+                .read_to_string(&mut input)
+                .map_err(|err| format!("Failed to read input for day {day} from stdin: {err}"))?;
+            Ok(input)
+        }
+        Some(path) => std::fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read input for day {day} from {path}: {err}")),
+        None => embedded_input(day).map(str::to_string).ok_or_else(|| {
+            format!(
+                "No input for day {day}: pass --input <path> or add inputs/{day}.txt and rebuild"
+            )
+        }),
+    }
+}
+
+/// Result of checking an answer against the stored one in `answers/<day>.txt`,
+/// which holds the part 1 and part 2 answers on separate lines.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    Pass,
+    Fail(String),
+    Unknown,
+    /// The solver returned an error instead of an answer.
+    Error,
+}
+
+impl Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Verdict::Pass => write!(f, "PASS"),
+            Verdict::Fail(_) => write!(f, "FAIL"),
+            Verdict::Unknown => write!(f, "UNKNOWN"),
+            Verdict::Error => write!(f, "ERROR"),
+        }
+    }
+}
+
+fn check_answer(answers: Option<&str>, part: u32, answer: &str) -> Verdict {
+    let expected = answers
+        .and_then(|answers| answers.lines().nth(part as usize - 1))
+        .map(str::trim)
+        .filter(|expected| !expected.is_empty());
+    match expected {
+        None => Verdict::Unknown,
+        Some(expected) if expected == answer => Verdict::Pass,
+        Some(expected) => Verdict::Fail(expected.to_string()),
+    }
+}
+
+pub fn verify(day: u32, part: u32, answer: &str) -> Verdict {
+    check_answer(embedded_answers(&day.to_string()), part, answer)
+}
+
+/// Summary of repeated timings, in seconds.
+#[derive(Debug, PartialEq)]
+pub struct Stats {
+    pub min: f64,
+    pub median: f64,
+    pub mean: f64,
+    pub stddev: f64,
+}
+
+impl Stats {
+    fn new(mut samples: Vec<f64>) -> Self {
+        samples.sort_by(f64::total_cmp);
+        let n = samples.len();
+        let median = if n.is_multiple_of(2) {
+            (samples[n / 2 - 1] + samples[n / 2]) / 2.0
+        } else {
+            samples[n / 2]
+        };
+        let mean = samples.iter().sum::<f64>() / n as f64;
+        let variance = samples.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / n as f64;
+        Stats {
+            min: samples[0],
+            median,
+            mean,
+            stddev: variance.sqrt(),
+        }
+    }
+
+    /// Warms up with a tenth as many runs, then times `runs` calls of `f`.
+    pub fn measure(runs: usize, f: impl Fn()) -> Self {
+        for _ in 0..(runs / 10).max(1) {
+            f();
+        }
+        Stats::new(
+            (0..runs.max(1))
+                .map(|_| {
+                    let start = Instant::now();
+                    f();
+                    start.elapsed().as_secs_f64()
+                })
+                .collect(),
+        )
+    }
+}
+
+fn format_duration(seconds: f64) -> String {
+    match seconds {
+        s if s < 1e-6 => format!("{:.1}ns", s * 1e9),
+        s if s < 1e-3 => format!("{:.1}µs", s * 1e6),
+        s if s < 1.0 => format!("{:.3}ms", s * 1e3),
+        s => format!("{s:.3}s"),
+    }
+}
+
+fn bench(
+    report: &mut Report,
+    day: &dyn Registered,
+    part: u32,
+    input: &str,
+    runs: usize,
+) -> Result<(), Error> {
+    let (parse, solve) = day.bench(input, part, runs)?;
+    for (stage, stats) in [("parse", parse), ("solve", solve)] {
+        let text = format!(
+            "{:>3}  {:>4}  {:<5}  {:>10}  {:>10}  {:>10}  {:>10}",
+            day.day(),
+            part,
+            stage,
+            format_duration(stats.min),
+            format_duration(stats.median),
+            format_duration(stats.mean),
+            format_duration(stats.stddev),
+        );
+        report.row(
+            vec![
+                Field::Int(day.day() as u64),
+                Field::Int(part as u64),
+                Field::Text(stage.to_string()),
+                Field::Seconds(stats.min),
+                Field::Seconds(stats.median),
+                Field::Seconds(stats.mean),
+                Field::Seconds(stats.stddev),
+            ],
+            text,
+        );
+    }
+    Ok(())
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Text,
+    Json,
+    Csv,
+}
+
+enum Field {
+    Int(u64),
+    Seconds(f64),
+    Text(String),
+    Missing,
+}
+
+impl Field {
+    fn json(&self) -> String {
+        match self {
+            Field::Int(n) => n.to_string(),
+            Field::Seconds(s) => s.to_string(),
+            Field::Text(text) => {
+                let mut escaped = String::from('"');
+                for c in text.chars() {
+                    match c {
+                        '"' => escaped += "\\\"",
+                        '\\' => escaped += "\\\\",
+                        c if c.is_control() => escaped += &format!("\\u{:04x}", c as u32),
+                        c => escaped.push(c),
+                    }
+                }
+                escaped + "\""
+            }
+            Field::Missing => "null".to_string(),
+        }
+    }
+
+    fn csv(&self) -> String {
+        match self {
+            Field::Text(text) if text.contains([',', '"', '\n']) => {
+                format!("\"{}\"", text.replace('"', "\"\""))
+            }
+            Field::Text(text) => text.clone(),
+            Field::Missing => String::new(),
+            field => field.json(),
+        }
+    }
+}
+
+/// Prints rows either as human-readable text or as structured records.
+struct Report {
+    format: Format,
+    columns: &'static [&'static str],
+    records: Vec<String>,
+}
+
+impl Report {
+    fn new(format: Format, columns: &'static [&'static str], text_header: Option<String>) -> Self {
+        match format {
+            Format::Text => text_header
+                .into_iter()
+                .for_each(|header| println!("{header}")),
+            Format::Csv => println!("{}", columns.join(",")),
+            Format::Json => (),
+        }
+        Report {
+            format,
+            columns,
+            records: Vec::new(),
+        }
+    }
+
+    fn row(&mut self, fields: Vec<Field>, text: String) {
+        match self.format {
+            Format::Text => println!("{text}"),
+            Format::Csv => println!("{}", fields.iter().map(Field::csv).join(",")),
+            Format::Json => {
+                let record = zip(self.columns, &fields)
+                    .map(|(column, field)| format!("\"{column}\": {}", field.json()))
+                    .join(", ");
+                self.records.push(format!("{{{record}}}"));
+            }
+        }
+    }
+
+    fn text(&self, text: String) {
+        if self.format == Format::Text {
+            println!("{text}");
+        }
+    }
+
+    fn finish(self) {
+        if self.format == Format::Json {
+            println!("[");
+            for (i, record) in self.records.iter().enumerate() {
+                let comma = if i + 1 < self.records.len() { "," } else { "" };
+                println!("  {record}{comma}");
+            }
+            println!("]");
+        }
+    }
+}
+
+/// Which of the registered days to run: "latest", "all", a single part
+/// ("day5", "day5-2") or a range of whole days ("5", "5..12", "5..=12").
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Selection {
+    Latest,
+    All,
+    Part(u32, u32),
+    Days(u32, u32),
+}
+
+impl FromStr for Selection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let number = |n: &str| {
+            n.parse::<u32>()
+                .map_err(|_| format!("Invalid day {n:?} in task {s:?}"))
+        };
+        if s == "latest" {
+            Ok(Selection::Latest)
+        } else if s == "all" {
+            Ok(Selection::All)
+        } else if let Some((start, end)) = s.split_once("..=") {
+            Ok(Selection::Days(number(start)?, number(end)?))
+        } else if let Some((start, end)) = s.split_once("..") {
+            Ok(Selection::Days(
+                number(start)?,
+                number(end)?.saturating_sub(1),
+            ))
+        } else if let Some(day) = s.strip_prefix("day") {
+            match day.split_once('-') {
+                None => Ok(Selection::Part(number(day)?, 1)),
+                Some((day, "2")) => Ok(Selection::Part(number(day)?, 2)),
+                Some(_) => Err(format!("Invalid part in task {s:?}")),
+            }
+        } else {
+            let day = number(s)?;
+            Ok(Selection::Days(day, day))
+        }
+    }
+}
+
+impl Display for Selection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Selection::Latest => write!(f, "latest"),
+            Selection::All => write!(f, "all"),
+            Selection::Part(day, 1) => write!(f, "day{day}"),
+            Selection::Part(day, part) => write!(f, "day{day}-{part}"),
+            Selection::Days(start, end) => write!(f, "{start}..={end}"),
+        }
+    }
+}
+
+impl Selection {
+    fn select<'a>(&self, days: &[&'a dyn Registered]) -> Vec<(&'a dyn Registered, u32)> {
+        let all = days.iter().flat_map(|&day| [(day, 1), (day, 2)]);
+        match *self {
+            Selection::Latest => all.last().into_iter().collect(),
+            Selection::All => all.collect(),
+            Selection::Part(n, part) => all
+                .filter(|&(day, p)| (day.day(), p) == (n, part))
+                .collect(),
+            Selection::Days(start, end) => all
+                .filter(|(day, _)| (start..=end).contains(&day.day()))
+                .collect(),
+        }
+    }
+}
+
+pub fn list(days: &[&dyn Registered], format: Format) {
+    let mut report = Report::new(
+        format,
+        &["day", "title"],
+        Some(format!("{:>3}  Title", "Day")),
+    );
+    for day in days {
+        let title = day.title().unwrap_or_default();
+        report.row(
+            vec![
+                Field::Int(day.day() as u64),
+                day.title()
+                    .map_or(Field::Missing, |title| Field::Text(title.to_string())),
+            ],
+            format!("{:>3}  {title}", day.day()),
+        );
+    }
+    report.finish();
+}
+
+/// Applies `f` to every item on a pool of up to `threads` threads, returning
+/// the results in the same order as `items`.
+pub fn par_map<T, R, F>(items: &[T], threads: usize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<Option<R>> = items.iter().map(|_| None).collect();
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads.clamp(1, items.len().max(1)))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(item) = items.get(i) else {
+                            return done;
+                        };
+                        done.push((i, f(item)));
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            for (i, result) in worker.join().unwrap() {
+                results[i] = Some(result);
+            }
+        }
+    });
+    results.into_iter().map(Option::unwrap).collect()
+}
+
+pub fn run(
+    days: &[&dyn Registered],
+    selection: &Selection,
+    path: Option<&str>,
+    bench_runs: Option<usize>,
+    jobs: usize,
+    format: Format,
+    verbose: bool,
+) {
+    let selected = selection.select(days);
+    let Some(&(first, _)) = selected.first() else {
+        eprintln!("No days match task {selection}");
+        exit(1);
+    };
+    if path.is_some() && selected.iter().any(|(day, _)| day.day() != first.day()) {
+        eprintln!("--input can only be used when running a single day");
+        exit(1);
+    }
+    let table = bench_runs.is_some() || selected.len() > 1;
+    let mut report = match bench_runs {
+        Some(_) => Report::new(
+            format,
+            &["day", "part", "stage", "min", "median", "mean", "stddev"],
+            Some(format!(
+                "{:>3}  {:>4}  {:<5}  {:>10}  {:>10}  {:>10}  {:>10}",
+                "Day", "Part", "Stage", "Min", "Median", "Mean", "Stddev"
+            )),
+        ),
+        None => Report::new(
+            format,
+            &["day", "part", "answer", "seconds", "status", "expected"],
+            table.then(|| {
+                format!(
+                    "{:>3}  {:>4}  {:<20}  {:>8}  Status",
+                    "Day", "Part", "Answer", "Seconds"
+                )
+            }),
+        ),
+    };
+    let mut failed = false;
+    // Load every input up front so that the solvers can share them.
+    let mut inputs: Vec<(u32, String)> = Vec::new();
+    let mut tasks = Vec::new();
+    for (day, part) in selected {
+        if inputs.last().map(|&(n, _)| n) != Some(day.day()) {
+            match load_input(path, &day.day().to_string()) {
+                Ok(text) => inputs.push((day.day(), text)),
+                Err(err) => {
+                    eprintln!("{err}");
+                    failed = true;
+                    continue;
+                }
+            }
+        }
+        tasks.push((day, part, inputs.len() - 1));
+    }
+    if let Some(runs) = bench_runs {
+        // Benchmarks run one at a time so they don't compete for the CPU.
+        for &(day, part, i) in &tasks {
+            if let Err(err) = bench(&mut report, day, part, &inputs[i].1, runs) {
+                eprintln!("Day {} part {part}: {err}", day.day());
+                failed = true;
+            }
+        }
+    }
+    // Wall-clock time, since the solvers overlap with more than one job. Each
+    // part's own time is then only comparable with runs using as many jobs.
+    let start = Instant::now();
+    let results = match bench_runs {
+        Some(_) => Vec::new(),
+        None => par_map(&tasks, jobs, |&(day, part, i)| {
+            let start = Instant::now();
+            let log = Log::new(day.day(), part, verbose);
+            let result = day.solve(&inputs[i].1, part, &log);
+            (result, start.elapsed().as_secs_f64())
+        }),
+    };
+    let total = start.elapsed().as_secs_f64();
+    for (&(day, part, _), (result, duration)) in zip(&tasks, results) {
+        let (result, verdict) = match result {
+            Ok(answer) => {
+                let answer = answer.to_string();
+                // The stored answers only apply to the embedded inputs.
+                let verdict = match path {
+                    None => verify(day.day(), part, &answer),
+                    Some(_) => Verdict::Unknown,
+                };
+                (Some(answer), verdict)
+            }
+            Err(err) => {
+                eprintln!("Day {} part {part}: {err}", day.day());
+                failed = true;
+                (None, Verdict::Error)
+            }
+        };
+        let answer = result.as_deref().unwrap_or("-");
+        let text = if table {
+            format!(
+                "{:>3}  {:>4}  {:<20}  {:>8.3}  {}",
+                day.day(),
+                part,
+                answer,
+                duration,
+                verdict
+            )
+        } else {
+            let label = match part {
+                1 => day.day().to_string(),
+                part => format!("{} (part {part})", day.day()),
+            };
+            format!(
+                "Computed result for day {label} in {duration:.3} seconds: {answer} ({verdict})"
+            )
+        };
+        let expected = match &verdict {
+            Verdict::Fail(expected) => Field::Text(expected.clone()),
+            _ => Field::Missing,
+        };
+        report.row(
+            vec![
+                Field::Int(day.day() as u64),
+                Field::Int(part as u64),
+                result.clone().map_or(Field::Missing, Field::Text),
+                Field::Seconds(duration),
+                Field::Text(verdict.to_string()),
+                expected,
+            ],
+            text,
+        );
+        if let Verdict::Fail(expected) = verdict {
+            eprintln!(
+                "Day {} part {part} answered {answer} but expected {expected}",
+                day.day()
+            );
+            failed = true;
+        }
+    }
+    if table && bench_runs.is_none() {
+        report.text(format!("Total time: {total:.3} seconds"));
+    }
+    report.finish();
+    if failed {
+        exit(1);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_selection() {
+        assert_eq!("all".parse(), Ok(Selection::All));
+        assert_eq!("latest".parse(), Ok(Selection::Latest));
+        assert_eq!("5..=12".parse(), Ok(Selection::Days(5, 12)));
+        assert_eq!("5..12".parse(), Ok(Selection::Days(5, 11)));
+        assert_eq!("7".parse(), Ok(Selection::Days(7, 7)));
+        assert_eq!("day7".parse(), Ok(Selection::Part(7, 1)));
+        assert_eq!("day7-2".parse(), Ok(Selection::Part(7, 2)));
+        assert!("day7-3".parse::<Selection>().is_err());
+        assert!("soon".parse::<Selection>().is_err());
+    }
+
+    #[test]
+    fn test_stats() {
+        let stats = Stats::new(vec![4.0, 1.0, 3.0, 2.0]);
+        assert_eq!(stats.min, 1.0);
+        assert_eq!(stats.median, 2.5);
+        assert_eq!(stats.mean, 2.5);
+        assert!((stats.stddev - 1.25f64.sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_fields() {
+        assert_eq!(Field::Int(5).json(), "5");
+        assert_eq!(Field::Text("a\"b\\c\n".into()).json(), r#""a\"b\\c\u000a""#);
+        assert_eq!(Field::Text("a,\"b\"".into()).csv(), r#""a,""b""""#);
+        assert_eq!(Field::Missing.json(), "null");
+        assert_eq!(Field::Missing.csv(), "");
+    }
+
+    #[test]
+    fn test_check_answer() {
+        let answers = Some("142\n281\n");
+        assert_eq!(check_answer(answers, 1, "142"), Verdict::Pass);
+        assert_eq!(check_answer(answers, 2, "280"), Verdict::Fail("281".into()));
+        assert_eq!(check_answer(Some("142\n"), 2, "281"), Verdict::Unknown);
+        assert_eq!(check_answer(None, 1, "142"), Verdict::Unknown);
+    }
+
+    #[test]
+    fn test_par_map() {
+        let items: Vec<u64> = (0..100).collect();
+        let squares: Vec<_> = items.iter().map(|n| n * n).collect();
+        for threads in [0, 1, 3, 200] {
+            assert_eq!(par_map(&items, threads, |n| n * n), squares);
+        }
+        assert_eq!(par_map(&[] as &[u64], 4, |n| n * n), Vec::<u64>::new());
+    }
+}
@@ -0,0 +1,288 @@
+use std::{fmt::Display, hint::black_box};
+
+use nom::{character::complete::multispace0, sequence::terminated, IResult, Offset};
+
+use crate::runner::Stats;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The input doesn't match the puzzle's format. `line` and `column` are
+    /// 1-based, and `text` is the whole offending line.
+    Parse {
+        line: usize,
+        column: usize,
+        text: String,
+        message: String,
+    },
+    /// The input parsed, but has no answer.
+    Solve(String),
+}
+
+impl Error {
+    /// Reports a problem at the start of `at`, which must be a slice of `input`.
+    pub fn parse(input: &str, at: &str, message: impl Into<String>) -> Self {
+        let offset = input.offset(at).min(input.len());
+        let line_start = input[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = input[offset..]
+            .find('\n')
+            .map_or(input.len(), |i| offset + i);
+        Error::Parse {
+            line: input[..offset].matches('\n').count() + 1,
+            column: input[line_start..offset].chars().count() + 1,
+            text: input[line_start..line_end].trim_end().to_string(),
+            message: message.into(),
+        }
+    }
+
+    pub fn solve(message: impl Into<String>) -> Self {
+        Error::Solve(message.into())
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Parse {
+                line,
+                column,
+                text,
+                message,
+            } => {
+                writeln!(f, "line {line}, column {column}: {message}")?;
+                writeln!(f, "    {text}")?;
+                write!(f, "    {:>column$}", "^")
+            }
+            Error::Solve(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Runs a nom parser that must consume the whole input, apart from trailing
+/// whitespace, reporting where it got stuck otherwise.
+pub fn parse_all<'a, O>(
+    input: &'a str,
+    parser: impl FnMut(&'a str) -> IResult<&'a str, O>,
+) -> Result<O> {
+    match terminated(parser, multispace0)(input) {
+        Ok(("", output)) => Ok(output),
+        Ok((rest, _)) => Err(Error::parse(input, rest, "unexpected input")),
+        Err(nom::Err::Error(err) | nom::Err::Failure(err)) => {
+            let at = err.input.trim_start();
+            let message = match at.chars().next() {
+                Some(c) => format!("unexpected {c:?} ({:?})", err.code),
+                None => return Err(unexpected_end(input)),
+            };
+            Err(Error::parse(input, at, message))
+        }
+        Err(nom::Err::Incomplete(_)) => Err(Error::parse(
+            input,
+            &input[input.len()..],
+            "incomplete input",
+        )),
+    }
+}
+
+/// Reports that `input` stopped before the puzzle was complete.
+pub fn unexpected_end(input: &str) -> Error {
+    Error::parse(input, &input[input.len()..], "unexpected end of input")
+}
+
+/// Reports an unexpected character at the start of `at` in a grid puzzle.
+pub fn bad_char(input: &str, at: &str) -> Error {
+    let c = at.chars().next().unwrap_or_default();
+    Error::parse(input, at, format!("unexpected character {c:?}"))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Answer {
+    Unsigned(u64),
+    Signed(i64),
+    Text(String),
+}
+
+impl Display for Answer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Answer::Unsigned(n) => write!(f, "{n}"),
+            Answer::Signed(n) => write!(f, "{n}"),
+            Answer::Text(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+impl From<u32> for Answer {
+    fn from(n: u32) -> Self {
+        Answer::Unsigned(n as u64)
+    }
+}
+
+impl From<usize> for Answer {
+    fn from(n: usize) -> Self {
+        Answer::Unsigned(n as u64)
+    }
+}
+
+impl From<i64> for Answer {
+    fn from(n: i64) -> Self {
+        Answer::Signed(n)
+    }
+}
+
+impl From<String> for Answer {
+    fn from(s: String) -> Self {
+        Answer::Text(s)
+    }
+}
+
+// Lets tests compare answers against plain integer literals.
+impl PartialEq<i64> for Answer {
+    fn eq(&self, other: &i64) -> bool {
+        match *self {
+            Answer::Unsigned(n) => i64::try_from(n) == Ok(*other),
+            Answer::Signed(n) => n == *other,
+            Answer::Text(_) => false,
+        }
+    }
+}
+
+/// Somewhere for a solver to report intermediate values, like the lengths of
+/// cycles it found. They go to stderr, tagged with the day and part, and only
+/// when the runner was asked to be verbose.
+#[derive(Clone, Copy, Debug)]
+pub struct Log {
+    day: u32,
+    part: u32,
+    verbose: bool,
+}
+
+impl Log {
+    pub fn new(day: u32, part: u32, verbose: bool) -> Self {
+        Log { day, part, verbose }
+    }
+
+    /// A log that drops everything.
+    pub fn quiet() -> Self {
+        Log::new(0, 0, false)
+    }
+
+    pub fn is_verbose(&self) -> bool {
+        self.verbose
+    }
+
+    /// Writes `message` if verbose. Pass `format_args!` to skip formatting
+    /// otherwise.
+    pub fn note(&self, message: impl Display) {
+        if self.verbose {
+            eprintln!("day {} part {}: {message}", self.day, self.part);
+        }
+    }
+}
+
+/// A day's puzzle, split into parsing the input and answering each part from
+/// the parsed form.
+pub trait Solution {
+    const DAY: u32;
+    const TITLE: Option<&'static str> = None;
+
+    type Input<'a>;
+
+    fn parse(input: &str) -> Result<Self::Input<'_>>;
+    fn part1(input: &Self::Input<'_>, log: &Log) -> Result<Answer>;
+    fn part2(input: &Self::Input<'_>, log: &Log) -> Result<Answer>;
+
+    fn solve(input: &str) -> Result<Answer> {
+        Self::part1(&Self::parse(input)?, &Log::quiet())
+    }
+
+    fn solve_2(input: &str) -> Result<Answer> {
+        Self::part2(&Self::parse(input)?, &Log::quiet())
+    }
+}
+
+/// Object-safe view of a [`Solution`], so the days can be kept in one registry.
+pub trait Registered: Sync {
+    fn day(&self) -> u32;
+    fn title(&self) -> Option<&'static str>;
+    fn solve(&self, input: &str, part: u32, log: &Log) -> Result<Answer>;
+    /// Times parsing and solving `part` separately over `runs` runs each.
+    fn bench(&self, input: &str, part: u32, runs: usize) -> Result<(Stats, Stats)>;
+}
+
+fn part<S: Solution>(part: u32) -> fn(&S::Input<'_>, &Log) -> Result<Answer> {
+    match part {
+        1 => S::part1,
+        2 => S::part2,
+        _ => panic!("day {} has no part {part}", S::DAY),
+    }
+}
+
+impl<S: Solution + Sync> Registered for S {
+    fn day(&self) -> u32 {
+        S::DAY
+    }
+
+    fn title(&self) -> Option<&'static str> {
+        S::TITLE
+    }
+
+    fn solve(&self, input: &str, n: u32, log: &Log) -> Result<Answer> {
+        part::<S>(n)(&S::parse(input)?, log)
+    }
+
+    fn bench(&self, input: &str, n: u32, runs: usize) -> Result<(Stats, Stats)> {
+        let solve = part::<S>(n);
+        let parsed = S::parse(input)?;
+        let log = Log::quiet();
+        solve(&parsed, &log)?;
+        let parse = Stats::measure(runs, || {
+            let _ = black_box(S::parse(input));
+        });
+        Ok((
+            parse,
+            Stats::measure(runs, || drop(black_box(solve(&parsed, &log)))),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use nom::{
+        bytes::complete::tag, character::complete::digit1, multi::many1, sequence::preceded,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_error_location() {
+        let input = "ab\ncde\nf";
+        let err = Error::parse(input, &input[4..], "bad");
+        assert_eq!(
+            err,
+            Error::Parse {
+                line: 2,
+                column: 2,
+                text: "cde".to_string(),
+                message: "bad".to_string()
+            }
+        );
+        assert_eq!(err.to_string(), "line 2, column 2: bad\n    cde\n     ^");
+    }
+
+    #[test]
+    fn test_parse_all() {
+        let numbers = |input| many1(preceded(multispace0, digit1))(input);
+        assert_eq!(parse_all("1\n2\n", numbers), Ok(vec!["1", "2"]));
+        let Err(Error::Parse { line, column, .. }) = parse_all("1\n  x2", numbers) else {
+            panic!()
+        };
+        assert_eq!((line, column), (2, 3));
+        let Err(Error::Parse { line, column, .. }) = parse_all("a", tag("b")) else {
+            panic!()
+        };
+        assert_eq!((line, column), (1, 1));
+    }
+}
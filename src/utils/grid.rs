@@ -0,0 +1,324 @@
+use std::{
+    fmt::Display,
+    ops::{Index, IndexMut},
+};
+
+use glam::{IVec2, Vec2Swizzles};
+
+use crate::solution::{bad_char, unexpected_end, Error, Result};
+
+/// Up, right, down, left.
+pub const ORTHOGONAL: [IVec2; 4] = [IVec2::NEG_Y, IVec2::X, IVec2::Y, IVec2::NEG_X];
+
+/// The orthogonal directions followed by the diagonals.
+pub const ALL_DIRECTIONS: [IVec2; 8] = [
+    IVec2::NEG_Y,
+    IVec2::X,
+    IVec2::Y,
+    IVec2::NEG_X,
+    IVec2::new(1, -1),
+    IVec2::new(1, 1),
+    IVec2::new(-1, 1),
+    IVec2::new(-1, -1),
+];
+
+/// A rectangular grid stored row by row, indexed by `IVec2` with x going
+/// right and y going down from the top left corner.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, cells: Vec<T>) -> Self {
+        assert_eq!(cells.len(), width * height, "grid is the wrong size");
+        Grid {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    pub fn filled(width: usize, height: usize, value: T) -> Self
+    where
+        T: Clone,
+    {
+        Grid::new(width, height, vec![value; width * height])
+    }
+
+    /// Parses one row per line, ignoring indentation and blank lines before and
+    /// after the grid. A blank line inside it is an error, like any other short
+    /// row. `cell` returns `None` for characters that aren't allowed.
+    pub fn parse(input: &str, cell: impl FnMut(char) -> Option<T>) -> Result<Self> {
+        Grid::parse_lines(input, input.trim().lines(), cell)
+    }
+
+    /// Like [`Grid::parse`], but only reads `lines`, which must be slices of
+    /// `input` so that errors can point into it.
+    pub fn parse_lines<'a>(
+        input: &'a str,
+        lines: impl IntoIterator<Item = &'a str>,
+        mut cell: impl FnMut(char) -> Option<T>,
+    ) -> Result<Self> {
+        let mut cells = Vec::new();
+        let (mut width, mut height) = (0, 0);
+        for line in lines {
+            let line = line.trim();
+            let start = cells.len();
+            for (x, c) in line.char_indices() {
+                cells.push(cell(c).ok_or_else(|| bad_char(input, &line[x..]))?);
+            }
+            let len = cells.len() - start;
+            if height == 0 {
+                width = len;
+            } else if len != width {
+                return Err(Error::parse(input, line, "rows have different lengths"));
+            }
+            height += 1;
+        }
+        if cells.is_empty() {
+            return Err(unexpected_end(input));
+        }
+        Ok(Grid::new(width, height, cells))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The position one past the bottom right corner.
+    pub fn size(&self) -> IVec2 {
+        IVec2::new(self.width as i32, self.height as i32)
+    }
+
+    pub fn contains(&self, p: IVec2) -> bool {
+        (0..self.width as i32).contains(&p.x) && (0..self.height as i32).contains(&p.y)
+    }
+
+    fn offset(&self, p: IVec2) -> Option<usize> {
+        self.contains(p)
+            .then(|| p.y as usize * self.width + p.x as usize)
+    }
+
+    pub fn get(&self, p: IVec2) -> Option<&T> {
+        self.offset(p).map(|i| &self.cells[i])
+    }
+
+    pub fn get_mut(&mut self, p: IVec2) -> Option<&mut T> {
+        self.offset(p).map(|i| &mut self.cells[i])
+    }
+
+    /// Treats the grid as tiling the whole plane.
+    pub fn get_wrapping(&self, p: IVec2) -> &T {
+        &self[p.rem_euclid(self.size())]
+    }
+
+    /// Every position, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = IVec2> {
+        let width = self.width;
+        (0..self.cells.len()).map(move |i| position(width, i))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (IVec2, &T)> {
+        self.positions().zip(&self.cells)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.cells.iter()
+    }
+
+    /// The first position, row by row, whose cell matches.
+    pub fn find(&self, mut f: impl FnMut(&T) -> bool) -> Option<IVec2> {
+        self.iter().find(|(_, cell)| f(cell)).map(|(p, _)| p)
+    }
+
+    /// The orthogonal neighbours of `p` that are inside the grid.
+    pub fn neighbours4(&self, p: IVec2) -> impl Iterator<Item = IVec2> + '_ {
+        ORTHOGONAL
+            .into_iter()
+            .map(move |d| p + d)
+            .filter(|&q| self.contains(q))
+    }
+
+    /// The orthogonal and diagonal neighbours of `p` that are inside the grid.
+    pub fn neighbours8(&self, p: IVec2) -> impl Iterator<Item = IVec2> + '_ {
+        ALL_DIRECTIONS
+            .into_iter()
+            .map(move |d| p + d)
+            .filter(|&q| self.contains(q))
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    /// Every row from the top, or none if the grid has no columns.
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // With no columns there are no cells, so any chunk size gives nothing.
+        self.cells.chunks(self.width.max(1))
+    }
+
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        assert!(x < self.width, "column {x} is out of bounds");
+        self.cells.iter().skip(x).step_by(self.width)
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(|x| self.column(x))
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid::new(self.width, self.height, self.cells.iter().map(f).collect())
+    }
+
+    /// Builds a grid of the given size, taking each cell from `f(position)`.
+    pub fn from_fn(width: usize, height: usize, f: impl FnMut(IVec2) -> T) -> Self {
+        let cells = (0..width * height)
+            .map(|i| position(width, i))
+            .map(f)
+            .collect();
+        Grid::new(width, height, cells)
+    }
+
+    /// Swaps rows and columns.
+    pub fn transpose(&self) -> Self
+    where
+        T: Clone,
+    {
+        Grid::from_fn(self.height, self.width, |p| self[p.yx()].clone())
+    }
+
+    /// Rotates a quarter turn clockwise.
+    pub fn rotate_cw(&self) -> Self
+    where
+        T: Clone,
+    {
+        let h = self.height as i32;
+        Grid::from_fn(self.height, self.width, |p| {
+            self[IVec2::new(p.y, h - 1 - p.x)].clone()
+        })
+    }
+
+    /// Rotates a quarter turn anticlockwise.
+    pub fn rotate_ccw(&self) -> Self
+    where
+        T: Clone,
+    {
+        let w = self.width as i32;
+        Grid::from_fn(self.height, self.width, |p| {
+            self[IVec2::new(w - 1 - p.y, p.x)].clone()
+        })
+    }
+}
+
+fn position(width: usize, i: usize) -> IVec2 {
+    IVec2::new((i % width) as i32, (i / width) as i32)
+}
+
+impl<T> Index<IVec2> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, p: IVec2) -> &T {
+        match self.get(p) {
+            Some(cell) => cell,
+            None => panic!("{p} is outside the {}x{} grid", self.width, self.height),
+        }
+    }
+}
+
+impl<T> IndexMut<IVec2> for Grid<T> {
+    fn index_mut(&mut self, p: IVec2) -> &mut T {
+        let (width, height) = (self.width, self.height);
+        match self.get_mut(p) {
+            Some(cell) => cell,
+            None => panic!("{p} is outside the {width}x{height} grid"),
+        }
+    }
+}
+
+impl<T: Display> Display for Grid<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (y, row) in self.rows().enumerate() {
+            if y > 0 {
+                writeln!(f)?;
+            }
+            for cell in row {
+                write!(f, "{cell}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sample() -> Grid<char> {
+        Grid::parse(
+            "
+            abc
+            def",
+            Some,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let grid = sample();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid[IVec2::new(1, 1)], 'e');
+        assert_eq!(grid.get(IVec2::new(3, 0)), None);
+        assert_eq!(grid.get(IVec2::new(0, -1)), None);
+        assert_eq!(*grid.get_wrapping(IVec2::new(-1, 2)), 'c');
+        assert_eq!(grid.to_string(), "abc\ndef");
+
+        let digits = |c: char| c.to_digit(10);
+        let Err(Error::Parse { line, column, .. }) = Grid::parse("12\n3x", digits) else {
+            panic!()
+        };
+        assert_eq!((line, column), (2, 2));
+        assert!(Grid::parse("12\n345", digits).is_err());
+        assert!(Grid::parse("\n  \n", digits).is_err());
+        assert!(Grid::parse("\n19\n11\n\n", digits).is_ok());
+        assert!(Grid::parse("19\n\n11", digits).is_err());
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let grid = sample();
+        let corner: Vec<_> = grid.neighbours4(IVec2::ZERO).collect();
+        assert_eq!(corner, [IVec2::new(1, 0), IVec2::new(0, 1)]);
+        assert_eq!(grid.neighbours8(IVec2::new(1, 0)).count(), 5);
+    }
+
+    #[test]
+    fn test_views() {
+        let grid = sample();
+        assert_eq!(grid.row(1), ['d', 'e', 'f']);
+        assert_eq!(grid.column(2).collect::<String>(), "cf");
+        assert_eq!(grid.columns().count(), 3);
+        assert_eq!(grid.find(|&c| c == 'e'), Some(IVec2::new(1, 1)));
+
+        let empty = Grid::<char>::from_fn(0, 2, |_| '.');
+        assert_eq!(empty.rows().count(), 0);
+        assert_eq!(empty.transpose().to_string(), "");
+    }
+
+    #[test]
+    fn test_rotation() {
+        let grid = sample();
+        assert_eq!(grid.transpose().to_string(), "ad\nbe\ncf");
+        assert_eq!(grid.rotate_cw().to_string(), "da\neb\nfc");
+        assert_eq!(grid.rotate_ccw().to_string(), "cf\nbe\nad");
+        assert_eq!(grid.rotate_cw().rotate_ccw(), grid);
+        assert_eq!(grid.rotate_cw().rotate_cw().rotate_cw().rotate_cw(), grid);
+    }
+}
@@ -0,0 +1,170 @@
+use std::{fmt::Display, ops::Range};
+
+/// A set of integers, stored as sorted, disjoint half-open ranges with gaps
+/// between them.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct IntervalSet {
+    ranges: Vec<Range<i64>>,
+}
+
+impl IntervalSet {
+    pub fn new() -> Self {
+        IntervalSet::default()
+    }
+
+    pub fn ranges(&self) -> &[Range<i64>] {
+        &self.ranges
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// The number of integers in the set.
+    pub fn len(&self) -> i64 {
+        self.ranges.iter().map(|r| r.end - r.start).sum()
+    }
+
+    pub fn contains(&self, value: i64) -> bool {
+        let i = self.ranges.partition_point(|r| r.end <= value);
+        self.ranges.get(i).is_some_and(|r| r.contains(&value))
+    }
+
+    pub fn min(&self) -> Option<i64> {
+        self.ranges.first().map(|r| r.start)
+    }
+
+    pub fn max(&self) -> Option<i64> {
+        self.ranges.last().map(|r| r.end - 1)
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        self.ranges.iter().chain(&other.ranges).cloned().collect()
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let mut others = other.ranges.as_slice();
+        for r in &self.ranges {
+            // Neither list goes backwards, so the ranges that end before this
+            // one can't overlap any later one either.
+            while others.first().is_some_and(|o| o.end <= r.start) {
+                others = &others[1..];
+            }
+            for o in others.iter().take_while(|o| o.start < r.end) {
+                ranges.push(r.start.max(o.start)..r.end.min(o.end));
+            }
+        }
+        ranges.into_iter().collect()
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let mut others = other.ranges.as_slice();
+        for r in &self.ranges {
+            while others.first().is_some_and(|o| o.end <= r.start) {
+                others = &others[1..];
+            }
+            let mut start = r.start;
+            for o in others.iter().take_while(|o| o.start < r.end) {
+                ranges.push(start..o.start);
+                start = start.max(o.end);
+            }
+            ranges.push(start..r.end);
+        }
+        ranges.into_iter().collect()
+    }
+
+    /// Splits into the values below `at` and the rest.
+    pub fn split_at(&self, at: i64) -> (Self, Self) {
+        let below = IntervalSet::from(i64::MIN..at);
+        (self.intersection(&below), self.difference(&below))
+    }
+
+    /// Adds each piece's offset to the values inside its range, leaving values
+    /// outside every piece alone. The pieces must not overlap.
+    pub fn map_piecewise(&self, pieces: &[(Range<i64>, i64)]) -> Self {
+        let covered: IntervalSet = pieces.iter().map(|(r, _)| r.clone()).collect();
+        let mut ranges = self.difference(&covered).ranges;
+        for (r, offset) in pieces {
+            let inside = self.intersection(&IntervalSet::from(r.clone()));
+            ranges.extend(
+                inside
+                    .ranges
+                    .into_iter()
+                    .map(|r| r.start + offset..r.end + offset),
+            );
+        }
+        ranges.into_iter().collect()
+    }
+}
+
+impl From<Range<i64>> for IntervalSet {
+    fn from(range: Range<i64>) -> Self {
+        [range].into_iter().collect()
+    }
+}
+
+impl FromIterator<Range<i64>> for IntervalSet {
+    fn from_iter<I: IntoIterator<Item = Range<i64>>>(iter: I) -> Self {
+        let mut sorted: Vec<_> = iter.into_iter().filter(|r| !r.is_empty()).collect();
+        sorted.sort_by_key(|r| r.start);
+        let mut ranges: Vec<Range<i64>> = Vec::new();
+        for r in sorted {
+            match ranges.last_mut() {
+                Some(last) if r.start <= last.end => last.end = last.end.max(r.end),
+                _ => ranges.push(r),
+            }
+        }
+        IntervalSet { ranges }
+    }
+}
+
+impl Display for IntervalSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{{")?;
+        for (i, r) in self.ranges.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}..{}", r.start, r.end)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_normalise() {
+        let set: IntervalSet = [5..8, 0..2, 2..3, 7..10, 4..4].into_iter().collect();
+        assert_eq!(set.ranges(), [0..3, 5..10]);
+        assert_eq!(set.len(), 8);
+        assert_eq!((set.min(), set.max()), (Some(0), Some(9)));
+        assert!(set.contains(2) && !set.contains(3) && set.contains(9));
+        assert_eq!(set.to_string(), "{0..3, 5..10}");
+    }
+
+    #[test]
+    fn test_operations() {
+        let a: IntervalSet = [0..10, 20..30].into_iter().collect();
+        let b: IntervalSet = [5..25, 28..40].into_iter().collect();
+        assert_eq!(a.union(&b), IntervalSet::from(0..40));
+        assert_eq!(a.intersection(&b).ranges(), [5..10, 20..25, 28..30]);
+        assert_eq!(a.difference(&b).ranges(), [0..5, 25..28]);
+        assert_eq!(b.difference(&a).ranges(), [10..20, 30..40]);
+        let (below, rest) = a.split_at(25);
+        assert_eq!(below.ranges(), [0..10, 20..25]);
+        assert_eq!(rest, IntervalSet::from(25..30));
+    }
+
+    #[test]
+    fn test_map_piecewise() {
+        let set = IntervalSet::from(0..10);
+        let mapped = set.map_piecewise(&[(2..4, 100), (8..20, 12)]);
+        assert_eq!(mapped.ranges(), [0..2, 4..8, 20..22, 102..104]);
+        assert_eq!(mapped.len(), set.len());
+    }
+}
@@ -0,0 +1,820 @@
+//! A network of modules that pass high and low pulses to each other, one at
+//! a time in the order they're sent.
+
+use std::{
+    collections::{hash_map::Entry, BTreeMap, HashMap, VecDeque},
+    fmt::{Debug, Display},
+};
+
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{alpha1, digit1, multispace0, one_of},
+    combinator::{map, map_res, opt, value},
+    multi::{many0, many1, separated_list1},
+    sequence::{delimited, pair, preceded, terminated, tuple},
+    IResult,
+};
+
+use crate::solution::{parse_all, Error, Result};
+
+/// The last signal received from each module that sends to a gate.
+pub type Inputs<'a> = BTreeMap<&'a str, bool>;
+
+pub type Boxed<'a> = Box<dyn Behaviour<'a> + 'a>;
+
+/// What a kind of module does with the pulses it receives. Kinds from outside
+/// this module can be parsed by giving a [`Parser`] a [`Factory`] for them.
+pub trait Behaviour<'a>: CloneBehaviour<'a> + Debug {
+    /// Handles a pulse, returning the signal to send on, if any.
+    fn receive(&mut self, source: &str, signal: bool) -> Option<bool>;
+
+    /// The prefix that marks this kind of module in a network description.
+    fn symbol(&self) -> String;
+
+    /// Which of the built-in kinds this is, if it's one of them.
+    fn builtin(&self) -> Option<Builtin> {
+        None
+    }
+
+    /// Describes what the module remembers, if anything, for people to read.
+    fn state(&self) -> Option<String> {
+        None
+    }
+
+    /// Appends what the module remembers to `key`. Cycles are found by
+    /// comparing these, so equal states must write the same words and
+    /// different ones different words.
+    fn write_state(&self, key: &mut Vec<u64>);
+}
+
+/// The kinds of module that come with this module.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Builtin {
+    Broadcast,
+    FlipFlop,
+    Conjunction,
+    And,
+    Or,
+    Xor,
+    Counter,
+    Delay,
+}
+
+/// Lets boxed modules be cloned. Every [`Behaviour`] that's [`Clone`] gets it.
+pub trait CloneBehaviour<'a> {
+    fn clone_box(&self) -> Boxed<'a>;
+}
+
+impl<'a, B: Behaviour<'a> + Clone + 'a> CloneBehaviour<'a> for B {
+    fn clone_box(&self) -> Boxed<'a> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Boxed<'_> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+/// Packs `signals` into words, 64 to a word. Modules always have as many
+/// signals as they started with, so the count needn't be written.
+fn write_signals(key: &mut Vec<u64>, signals: impl Iterator<Item = bool>) {
+    for (i, signal) in signals.enumerate() {
+        if i % 64 == 0 {
+            key.push(0);
+        }
+        *key.last_mut().unwrap() |= (signal as u64) << (i % 64);
+    }
+}
+
+fn level(signal: bool) -> &'static str {
+    if signal {
+        "high"
+    } else {
+        "low"
+    }
+}
+
+/// Passes every pulse on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Broadcast;
+
+impl Behaviour<'_> for Broadcast {
+    fn receive(&mut self, _: &str, signal: bool) -> Option<bool> {
+        Some(signal)
+    }
+
+    fn symbol(&self) -> String {
+        String::new()
+    }
+
+    fn builtin(&self) -> Option<Builtin> {
+        Some(Builtin::Broadcast)
+    }
+
+    fn write_state(&self, _: &mut Vec<u64>) {}
+}
+
+/// Ignores high pulses, and flips on a low one, sending high if it's now on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FlipFlop {
+    pub on: bool,
+}
+
+impl Behaviour<'_> for FlipFlop {
+    fn receive(&mut self, _: &str, signal: bool) -> Option<bool> {
+        if signal {
+            return None;
+        }
+        self.on = !self.on;
+        Some(self.on)
+    }
+
+    fn symbol(&self) -> String {
+        "%".to_string()
+    }
+
+    fn builtin(&self) -> Option<Builtin> {
+        Some(Builtin::FlipFlop)
+    }
+
+    fn state(&self) -> Option<String> {
+        Some(if self.on { "on" } else { "off" }.to_string())
+    }
+
+    fn write_state(&self, key: &mut Vec<u64>) {
+        key.push(self.on as u64);
+    }
+}
+
+/// Remembers the last signal from each input, and sends what `output` makes
+/// of how many of them are high.
+#[derive(Clone, Debug)]
+pub struct Gate<'a> {
+    builtin: Builtin,
+    inputs: Inputs<'a>,
+    output: fn(high: usize, inputs: usize) -> bool,
+}
+
+impl<'a> Gate<'a> {
+    fn new(builtin: Builtin, inputs: &[&'a str], output: fn(usize, usize) -> bool) -> Self {
+        let inputs = inputs.iter().map(|&input| (input, false)).collect();
+        Gate {
+            builtin,
+            inputs,
+            output,
+        }
+    }
+
+    /// Sends low if every input is high, and high otherwise.
+    pub fn conjunction(inputs: &[&'a str]) -> Self {
+        Gate::new(Builtin::Conjunction, inputs, |high, n| high != n)
+    }
+
+    /// Sends high if every input is high, and low otherwise.
+    pub fn and(inputs: &[&'a str]) -> Self {
+        Gate::new(Builtin::And, inputs, |high, n| high == n)
+    }
+
+    /// Sends high if any input is high, and low otherwise.
+    pub fn or(inputs: &[&'a str]) -> Self {
+        Gate::new(Builtin::Or, inputs, |high, _| high > 0)
+    }
+
+    /// Sends high if an odd number of inputs are high, and low otherwise.
+    pub fn xor(inputs: &[&'a str]) -> Self {
+        Gate::new(Builtin::Xor, inputs, |high, _| high % 2 == 1)
+    }
+
+    pub fn inputs(&self) -> &Inputs<'a> {
+        &self.inputs
+    }
+}
+
+impl<'a> Behaviour<'a> for Gate<'a> {
+    fn receive(&mut self, source: &str, signal: bool) -> Option<bool> {
+        if let Some(input) = self.inputs.get_mut(source) {
+            *input = signal;
+        }
+        let high = self.inputs.values().filter(|&&v| v).count();
+        Some((self.output)(high, self.inputs.len()))
+    }
+
+    fn symbol(&self) -> String {
+        match self.builtin {
+            Builtin::Conjunction => "&",
+            Builtin::And => "*",
+            Builtin::Or => "|",
+            Builtin::Xor => "^",
+            _ => unreachable!("gates are only built as the gate kinds"),
+        }
+        .to_string()
+    }
+
+    fn builtin(&self) -> Option<Builtin> {
+        Some(self.builtin)
+    }
+
+    fn state(&self) -> Option<String> {
+        let inputs: Vec<_> = self
+            .inputs
+            .iter()
+            .map(|(input, &signal)| format!("{input}={}", level(signal)))
+            .collect();
+        Some(inputs.join(" "))
+    }
+
+    fn write_state(&self, key: &mut Vec<u64>) {
+        write_signals(key, self.inputs.values().copied());
+    }
+}
+
+/// Ignores high pulses and counts low ones, sending high on every `modulus`th
+/// and low otherwise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Counter {
+    modulus: usize,
+    count: usize,
+}
+
+impl Counter {
+    /// Panics if `modulus` is zero.
+    pub fn new(modulus: usize) -> Self {
+        assert!(modulus > 0, "counters need a modulus");
+        Counter { modulus, count: 0 }
+    }
+}
+
+impl Behaviour<'_> for Counter {
+    fn receive(&mut self, _: &str, signal: bool) -> Option<bool> {
+        if signal {
+            return None;
+        }
+        self.count = (self.count + 1) % self.modulus;
+        Some(self.count == 0)
+    }
+
+    fn symbol(&self) -> String {
+        format!("#{}", self.modulus)
+    }
+
+    fn builtin(&self) -> Option<Builtin> {
+        Some(Builtin::Counter)
+    }
+
+    fn state(&self) -> Option<String> {
+        Some(format!("{}/{}", self.count, self.modulus))
+    }
+
+    fn write_state(&self, key: &mut Vec<u64>) {
+        key.push(self.count as u64);
+    }
+}
+
+/// Sends the signal it received that many pulses ago, or low to begin with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Delay {
+    pending: VecDeque<bool>,
+}
+
+impl Delay {
+    pub fn new(pulses: usize) -> Self {
+        Delay {
+            pending: vec![false; pulses].into(),
+        }
+    }
+}
+
+impl Behaviour<'_> for Delay {
+    fn receive(&mut self, _: &str, signal: bool) -> Option<bool> {
+        self.pending.push_back(signal);
+        self.pending.pop_front()
+    }
+
+    fn symbol(&self) -> String {
+        format!("~{}", self.pending.len())
+    }
+
+    fn builtin(&self) -> Option<Builtin> {
+        Some(Builtin::Delay)
+    }
+
+    fn state(&self) -> Option<String> {
+        let pending: Vec<_> = self.pending.iter().map(|&signal| level(signal)).collect();
+        Some(pending.join(" "))
+    }
+
+    fn write_state(&self, key: &mut Vec<u64>) {
+        write_signals(key, self.pending.iter().copied());
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Module<'a> {
+    pub kind: Boxed<'a>,
+    pub dests: Vec<&'a str>,
+}
+
+pub type Network<'a> = HashMap<&'a str, Module<'a>>;
+
+/// Builds a module from the number written after its prefix, if any, and the
+/// names of the modules that send to it, or says what's wrong with them.
+pub type Factory = for<'a> fn(Option<usize>, &[&'a str]) -> Result<Boxed<'a>, String>;
+
+/// For the kinds whose prefix is all there is to them.
+fn no_number<'a>(
+    number: Option<usize>,
+    kind: impl Behaviour<'a> + 'a,
+) -> Result<Boxed<'a>, String> {
+    match number {
+        Some(_) => Err(format!("{} modules don't take a number", kind.symbol())),
+        None => Ok(Box::new(kind)),
+    }
+}
+
+fn flip_flop<'a>(number: Option<usize>, _: &[&'a str]) -> Result<Boxed<'a>, String> {
+    no_number(number, FlipFlop::default())
+}
+
+fn conjunction<'a>(number: Option<usize>, inputs: &[&'a str]) -> Result<Boxed<'a>, String> {
+    no_number(number, Gate::conjunction(inputs))
+}
+
+fn and<'a>(number: Option<usize>, inputs: &[&'a str]) -> Result<Boxed<'a>, String> {
+    no_number(number, Gate::and(inputs))
+}
+
+fn or<'a>(number: Option<usize>, inputs: &[&'a str]) -> Result<Boxed<'a>, String> {
+    no_number(number, Gate::or(inputs))
+}
+
+fn xor<'a>(number: Option<usize>, inputs: &[&'a str]) -> Result<Boxed<'a>, String> {
+    no_number(number, Gate::xor(inputs))
+}
+
+fn counter<'a>(number: Option<usize>, _: &[&'a str]) -> Result<Boxed<'a>, String> {
+    match number {
+        Some(0) | None => Err("counters need a modulus".to_string()),
+        Some(modulus) => Ok(Box::new(Counter::new(modulus))),
+    }
+}
+
+fn delay<'a>(number: Option<usize>, _: &[&'a str]) -> Result<Boxed<'a>, String> {
+    let pulses = number.ok_or("delays need a length")?;
+    Ok(Box::new(Delay::new(pulses)))
+}
+
+/// Parses networks, one module per line, as `name -> dest, ...` with a prefix
+/// before the name for anything but a broadcaster, and a number after the
+/// prefix for kinds that need one.
+#[derive(Clone, Debug)]
+pub struct Parser {
+    kinds: Vec<(char, Factory)>,
+}
+
+/// Knows `%` for a flip-flop, `&` for a conjunction, `*`, `|` and `^` for and,
+/// or and xor gates, `#n` for a counter modulo `n` and `~n` for a delay of `n`
+/// pulses.
+impl Default for Parser {
+    fn default() -> Self {
+        let kinds: [(char, Factory); 7] = [
+            ('%', flip_flop),
+            ('&', conjunction),
+            ('*', and),
+            ('|', or),
+            ('^', xor),
+            ('#', counter),
+            ('~', delay),
+        ];
+        Parser {
+            kinds: kinds.into(),
+        }
+    }
+}
+
+impl Parser {
+    /// Builds modules with `prefix` using `factory`, in place of any kind that
+    /// already had that prefix.
+    pub fn with(mut self, prefix: char, factory: Factory) -> Self {
+        self.kinds.retain(|&(c, _)| c != prefix);
+        self.kinds.push((prefix, factory));
+        self
+    }
+
+    pub fn parse<'a>(&self, input: &'a str) -> Result<Network<'a>> {
+        let prefixes: String = self.kinds.iter().map(|&(c, _)| c).collect();
+        let number = map_res(digit1, str::parse);
+        let prefix = pair(one_of(prefixes.as_str()), opt(number));
+        let line = pair(
+            pair(opt(prefix), alpha1),
+            preceded(tag(" -> "), separated_list1(tag(", "), alpha1)),
+        );
+        let modules = parse_all(input, many1(preceded(multispace0, line)))?;
+        let mut inputs = HashMap::<&str, Vec<&str>>::new();
+        for ((_, name), dests) in &modules {
+            for dest in dests {
+                inputs.entry(dest).or_default().push(name);
+            }
+        }
+        let mut result = HashMap::new();
+        for ((prefix, name), dests) in modules {
+            let kind: Boxed = match prefix {
+                None => Box::new(Broadcast),
+                Some((c, number)) => {
+                    let &(_, factory) = self.kinds.iter().find(|&&(k, _)| k == c).unwrap();
+                    let inputs = inputs.get(name).map_or(&[][..], Vec::as_slice);
+                    factory(number, inputs).map_err(|message| Error::parse(input, name, message))?
+                }
+            };
+            result.insert(name, Module { kind, dests });
+        }
+        Ok(result)
+    }
+}
+
+/// Parses a network with the built-in kinds of module.
+pub fn parse(input: &str) -> Result<Network<'_>> {
+    Parser::default().parse(input)
+}
+
+/// A pulse sent during the `press`th press of the button, which is the
+/// `source` of the first pulse of each press.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pulse<'a> {
+    pub press: usize,
+    pub source: &'a str,
+    pub signal: bool,
+    pub dest: &'a str,
+}
+
+impl Display for Pulse<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let signal = if self.signal { "high" } else { "low" };
+        write!(
+            f,
+            "{}: {} -{signal}-> {}",
+            self.press, self.source, self.dest
+        )
+    }
+}
+
+/// Parses a trace written one pulse per line, as [`Pulse`] displays them.
+pub fn parse_trace(input: &str) -> Result<Vec<Pulse<'_>>> {
+    fn pulse(input: &str) -> IResult<&str, Pulse<'_>> {
+        map(
+            tuple((
+                terminated(map_res(digit1, str::parse), tag(": ")),
+                alpha1,
+                delimited(
+                    tag(" -"),
+                    alt((value(false, tag("low")), value(true, tag("high")))),
+                    tag("-> "),
+                ),
+                alpha1,
+            )),
+            |(press, source, signal, dest)| Pulse {
+                press,
+                source,
+                signal,
+                dest,
+            },
+        )(input)
+    }
+    parse_all(input, many0(preceded(multispace0, pulse)))
+}
+
+/// Runs the network one pulse at a time, pressing the button whenever it
+/// goes quiet.
+#[derive(Clone, Debug)]
+pub struct Simulator<'a> {
+    modules: Network<'a>,
+    /// The modules in name order, for state keys.
+    names: Vec<&'a str>,
+    trigger: &'a str,
+    presses: usize,
+    queue: VecDeque<Pulse<'a>>,
+    trace: Option<Vec<Pulse<'a>>>,
+}
+
+impl<'a> Simulator<'a> {
+    /// Pressing the button sends a low pulse to `trigger`, which must be one
+    /// of the modules.
+    pub fn new(modules: Network<'a>, trigger: &'a str) -> Result<Self> {
+        if !modules.contains_key(trigger) {
+            return Err(Error::solve(format!("no module named {trigger}")));
+        }
+        let mut names: Vec<_> = modules.keys().copied().collect();
+        names.sort();
+        Ok(Simulator {
+            modules,
+            names,
+            trigger,
+            presses: 0,
+            queue: VecDeque::new(),
+            trace: None,
+        })
+    }
+
+    /// Keeps every pulse from now on, for [`Simulator::trace`].
+    pub fn record(&mut self) {
+        self.trace.get_or_insert_with(Vec::new);
+    }
+
+    pub fn trace(&self) -> &[Pulse<'a>] {
+        self.trace.as_deref().unwrap_or_default()
+    }
+
+    pub fn modules(&self) -> &Network<'a> {
+        &self.modules
+    }
+
+    pub fn presses(&self) -> usize {
+        self.presses
+    }
+
+    /// Whether every pulse from the last press has been delivered.
+    pub fn is_idle(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Delivers the next pulse, pressing the button first if there isn't
+    /// one, and returns it.
+    pub fn step(&mut self) -> Pulse<'a> {
+        if self.queue.is_empty() {
+            self.presses += 1;
+            self.queue.push_back(Pulse {
+                press: self.presses,
+                source: "button",
+                signal: false,
+                dest: self.trigger,
+            });
+        }
+        let pulse = self.queue.pop_front().unwrap();
+        if let Some(trace) = &mut self.trace {
+            trace.push(pulse);
+        }
+        let Some(module) = self.modules.get_mut(pulse.dest) else {
+            return pulse;
+        };
+        let Some(signal) = module.kind.receive(pulse.source, pulse.signal) else {
+            return pulse;
+        };
+        for &dest in &module.dests {
+            self.queue.push_back(Pulse {
+                press: self.presses,
+                source: pulse.dest,
+                signal,
+                dest,
+            });
+        }
+        pulse
+    }
+
+    /// Presses the button and delivers every pulse that follows.
+    pub fn press(&mut self, mut on_pulse: impl FnMut(&Pulse<'a>)) {
+        loop {
+            on_pulse(&self.step());
+            if self.is_idle() {
+                break;
+            }
+        }
+    }
+
+    /// Runs the network through `trace`, failing at the first pulse that
+    /// doesn't match.
+    pub fn replay(&mut self, trace: &[Pulse<'_>]) -> Result<()> {
+        for (i, expected) in trace.iter().enumerate() {
+            let actual = self.step();
+            if actual != *expected {
+                return Err(Error::solve(format!(
+                    "pulse {} should be {expected} but was {actual}",
+                    i + 1
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// What every module remembers, the same exactly when the states are.
+    pub fn state_key(&self) -> Vec<u64> {
+        let mut key = Vec::new();
+        for name in &self.names {
+            self.modules[name].kind.write_state(&mut key);
+        }
+        key
+    }
+
+    /// Presses the button up to `max_presses` times, until every module is
+    /// back in a state it was in after an earlier press. Returns that press
+    /// (0 for before the first) and how many presses the cycle takes.
+    pub fn find_cycle(&mut self, max_presses: usize) -> Option<(usize, usize)> {
+        while !self.is_idle() {
+            self.step();
+        }
+        let mut seen = HashMap::from([(self.state_key(), self.presses)]);
+        for _ in 0..max_presses {
+            self.press(|_| ());
+            match seen.entry(self.state_key()) {
+                Entry::Occupied(entry) => return Some((*entry.get(), self.presses - entry.get())),
+                Entry::Vacant(entry) => {
+                    entry.insert(self.presses);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Lists the state of every module that has one, in name order.
+impl Display for Simulator<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut names: Vec<_> = self.modules.keys().collect();
+        names.sort();
+        for name in names {
+            let kind = &self.modules[name].kind;
+            if let Some(state) = kind.state() {
+                writeln!(f, "{}{name} {state}", kind.symbol())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_simulator() {
+        const SAMPLE: &str = "
+        broadcaster -> a
+        %a -> inv, con
+        &inv -> b
+        %b -> con
+        &con -> output";
+        let mut simulator = Simulator::new(parse(SAMPLE).unwrap(), "broadcaster").unwrap();
+        simulator.record();
+        let first = simulator.step();
+        assert_eq!(first.to_string(), "1: button -low-> broadcaster");
+        assert!(!simulator.is_idle());
+        simulator.press(|_| ());
+        assert_eq!(simulator.trace().len(), 8);
+        assert_eq!(
+            simulator.to_string(),
+            "%a on\n%b on\n&con a=high b=high\n&inv a=high\n"
+        );
+        for _ in 0..3 {
+            simulator.press(|_| ());
+        }
+        assert_eq!(simulator.presses(), 4);
+
+        let text: Vec<_> = simulator.trace().iter().map(Pulse::to_string).collect();
+        let text = text.join("\n");
+        let trace = parse_trace(&text).unwrap();
+        assert_eq!(trace, simulator.trace());
+        let mut replay = Simulator::new(parse(SAMPLE).unwrap(), "broadcaster").unwrap();
+        assert!(replay.replay(&trace).is_ok());
+        let mut replay = Simulator::new(parse(SAMPLE).unwrap(), "broadcaster").unwrap();
+        assert!(replay.replay(&trace[1..]).is_err());
+
+        let missing = Simulator::new(parse(SAMPLE).unwrap(), "button").unwrap_err();
+        assert_eq!(missing.to_string(), "no module named button");
+    }
+
+    #[test]
+    fn test_gates() {
+        const NETWORK: &str = "
+        broadcaster -> a, b
+        %a -> and, or, xor
+        %b -> fb
+        %fb -> and, or, xor
+        *and -> out
+        |or -> out
+        ^xor -> out";
+        let mut simulator = Simulator::new(parse(NETWORK).unwrap(), "broadcaster").unwrap();
+        let mut outputs = Vec::new();
+        for _ in 0..4 {
+            let mut sent = HashMap::new();
+            simulator.press(|pulse| {
+                if pulse.dest == "out" {
+                    sent.insert(pulse.source, pulse.signal);
+                }
+            });
+            outputs.push([sent["and"], sent["or"], sent["xor"]]);
+        }
+        // a is on after odd presses, and fb after presses 2 and 3 mod 4.
+        assert_eq!(
+            outputs,
+            [
+                [false, true, true],
+                [false, true, true],
+                [true, true, false],
+                [false, false, false],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_counter_and_delay() {
+        const NETWORK: &str = "
+        start -> count, delay
+        #3count -> out
+        ~2delay -> out";
+        let modules = parse(NETWORK).unwrap();
+        assert_eq!(modules["count"].kind.symbol(), "#3");
+        let mut simulator = Simulator::new(modules, "start").unwrap();
+        let mut sent = Vec::new();
+        for _ in 0..3 {
+            simulator.press(|pulse| {
+                if pulse.dest == "out" {
+                    sent.push((pulse.source, pulse.signal));
+                }
+            });
+        }
+        let counted: Vec<_> = sent
+            .iter()
+            .filter(|(s, _)| *s == "count")
+            .map(|p| p.1)
+            .collect();
+        assert_eq!(counted, [false, false, true]);
+        assert_eq!(sent.iter().filter(|(s, _)| *s == "delay").count(), 3);
+        assert_eq!(simulator.to_string(), "#3count 0/3\n~2delay low low\n");
+        assert!(parse("#0count -> out").is_err());
+        assert!(parse("~delay -> out").is_err());
+        assert!(parse("%3flip -> out").is_err());
+    }
+
+    #[test]
+    fn test_custom_kind() {
+        /// Sends the opposite of every pulse.
+        #[derive(Clone, Debug)]
+        struct Not;
+
+        impl Behaviour<'_> for Not {
+            fn receive(&mut self, _: &str, signal: bool) -> Option<bool> {
+                Some(!signal)
+            }
+
+            fn symbol(&self) -> String {
+                "!".to_string()
+            }
+
+            fn write_state(&self, _: &mut Vec<u64>) {}
+        }
+
+        fn not<'a>(number: Option<usize>, _: &[&'a str]) -> Result<Boxed<'a>, String> {
+            match number {
+                Some(_) => Err("not gates don't take a number".to_string()),
+                None => Ok(Box::new(Not)),
+            }
+        }
+
+        const NETWORK: &str = "
+        start -> flip, not
+        %flip -> out
+        !not -> out";
+        assert!(parse(NETWORK).is_err());
+        let modules = Parser::default().with('!', not).parse(NETWORK).unwrap();
+        assert_eq!(modules["not"].kind.builtin(), None);
+        assert_eq!(modules["flip"].kind.builtin(), Some(Builtin::FlipFlop));
+        let mut simulator = Simulator::new(modules, "start").unwrap();
+        let mut sent = Vec::new();
+        simulator.press(|pulse| {
+            if pulse.dest == "out" {
+                sent.push((pulse.source, pulse.signal));
+            }
+        });
+        assert_eq!(sent, [("flip", true), ("not", true)]);
+        assert_eq!(simulator.to_string(), "%flip on\n");
+    }
+
+    #[test]
+    fn test_find_cycle() {
+        const NETWORK: &str = "
+        broadcaster -> a
+        %a -> b
+        %b -> c
+        %c -> out";
+        let mut simulator = Simulator::new(parse(NETWORK).unwrap(), "broadcaster").unwrap();
+        assert_eq!(simulator.find_cycle(100), Some((0, 8)));
+        let mut simulator = Simulator::new(parse(NETWORK).unwrap(), "broadcaster").unwrap();
+        assert_eq!(simulator.find_cycle(5), None);
+        let mut simulator = Simulator::new(parse(NETWORK).unwrap(), "broadcaster").unwrap();
+        // Stopping in the middle of the second press finishes it first.
+        simulator.press(|_| ());
+        simulator.step();
+        assert_eq!(simulator.find_cycle(100), Some((2, 8)));
+
+        // The delay and the conjunction remember the counter's earlier
+        // pulses, so the cycle starts later than the counter's does.
+        const MEMORY: &str = "
+        broadcaster -> n
+        #3n -> d
+        ~2d -> c
+        &c -> out";
+        let mut simulator = Simulator::new(parse(MEMORY).unwrap(), "broadcaster").unwrap();
+        assert_eq!(simulator.find_cycle(100), Some((3, 3)));
+    }
+}
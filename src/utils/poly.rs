@@ -0,0 +1,307 @@
+use std::{
+    fmt::Display,
+    ops::{Add, Div, Mul, Neg, Range, Sub},
+};
+
+use itertools::Itertools;
+
+/// An exact fraction, kept in lowest terms with a positive denominator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Ratio {
+    num: i128,
+    den: i128,
+}
+
+impl Ratio {
+    /// Panics if `den` is zero.
+    pub fn new(num: i128, den: i128) -> Self {
+        assert!(den != 0, "zero denominator");
+        let gcd = gcd(num, den);
+        let sign = den.signum();
+        Ratio {
+            num: sign * num / gcd,
+            den: sign * den / gcd,
+        }
+    }
+
+    pub fn numerator(&self) -> i128 {
+        self.num
+    }
+
+    pub fn denominator(&self) -> i128 {
+        self.den
+    }
+
+    /// Panics if this is zero.
+    pub fn recip(self) -> Self {
+        Ratio::new(self.den, self.num)
+    }
+
+    pub fn to_integer(&self) -> Option<i128> {
+        (self.den == 1).then_some(self.num)
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a.max(1)
+}
+
+impl From<i64> for Ratio {
+    fn from(n: i64) -> Self {
+        Ratio::new(n as i128, 1)
+    }
+}
+
+impl Add for Ratio {
+    type Output = Ratio;
+
+    fn add(self, other: Ratio) -> Ratio {
+        // Dividing through by the gcd first keeps the products small.
+        let g = gcd(self.den, other.den);
+        Ratio::new(
+            self.num * (other.den / g) + other.num * (self.den / g),
+            self.den / g * other.den,
+        )
+    }
+}
+
+impl Neg for Ratio {
+    type Output = Ratio;
+
+    fn neg(self) -> Ratio {
+        Ratio {
+            num: -self.num,
+            den: self.den,
+        }
+    }
+}
+
+impl Sub for Ratio {
+    type Output = Ratio;
+
+    fn sub(self, other: Ratio) -> Ratio {
+        self + -other
+    }
+}
+
+impl Mul for Ratio {
+    type Output = Ratio;
+
+    fn mul(self, other: Ratio) -> Ratio {
+        let a = gcd(self.num, other.den);
+        let b = gcd(other.num, self.den);
+        Ratio::new(
+            (self.num / a) * (other.num / b),
+            (self.den / b) * (other.den / a),
+        )
+    }
+}
+
+impl Div for Ratio {
+    type Output = Ratio;
+
+    /// Panics if `other` is zero.
+    fn div(self, other: Ratio) -> Ratio {
+        self.mul(other.recip())
+    }
+}
+
+impl Display for Ratio {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.den {
+            1 => write!(f, "{}", self.num),
+            den => write!(f, "{}/{den}", self.num),
+        }
+    }
+}
+
+/// The value at `x` of the lowest degree polynomial through `points`, whose
+/// x coordinates must be distinct.
+///
+/// The fractions aren't checked for overflow, so each `y` times the product of
+/// its point's distances to `x` and to the other points must fit in an `i128`.
+/// For three or four points that allows coordinates up to about `2^30`.
+pub fn lagrange(points: &[(i64, i64)], x: i64) -> Ratio {
+    let mut total = Ratio::from(0);
+    for (i, &(xi, yi)) in points.iter().enumerate() {
+        let mut term = Ratio::from(yi);
+        for (j, &(xj, _)) in points.iter().enumerate() {
+            if i != j {
+                let (x, xi, xj) = (x as i128, xi as i128, xj as i128);
+                term = term * Ratio::new(x - xj, xi - xj);
+            }
+        }
+        total = total + term;
+    }
+    total
+}
+
+/// The rows of forward differences of `values`, each one shorter than the last,
+/// ending at the first row of zeros or, if there isn't one, a single value.
+pub fn differences(values: &[i64]) -> Vec<Vec<i64>> {
+    let mut rows = vec![values.to_vec()];
+    loop {
+        let last = rows.last().unwrap();
+        if last.len() <= 1 || last.iter().all(|&n| n == 0) {
+            return rows;
+        }
+        let next = last.iter().tuple_windows().map(|(a, b)| b - a).collect();
+        rows.push(next);
+    }
+}
+
+/// The degree of the polynomial that `values` follow, if they have enough
+/// values to show it: one that ends in a row of zeros.
+pub fn degree(values: &[i64]) -> Option<usize> {
+    let rows = differences(values);
+    let last = rows.last().unwrap();
+    last.iter()
+        .all(|&n| n == 0)
+        .then(|| rows.len().saturating_sub(2))
+}
+
+/// A polynomial in Newton's forward difference form: the sum of
+/// `coefficients[k] * binomial(x, k)`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Newton {
+    coefficients: Vec<i64>,
+}
+
+impl Newton {
+    /// The lowest degree polynomial taking `values` at 0, 1, 2 and so on.
+    pub fn new(values: &[i64]) -> Self {
+        let mut coefficients: Vec<_> = differences(values)
+            .into_iter()
+            .filter_map(|row| row.first().copied())
+            .collect();
+        // A zero coefficient adds nothing, but its binomial can still overflow.
+        while coefficients.last() == Some(&0) {
+            coefficients.pop();
+        }
+        Newton { coefficients }
+    }
+
+    pub fn coefficients(&self) -> &[i64] {
+        &self.coefficients
+    }
+
+    /// The value at `x`, which may be before the first value or past the last,
+    /// or `None` if it or a binomial on the way to it overflows an `i128`.
+    pub fn at(&self, x: i64) -> Option<i128> {
+        let x = x as i128;
+        let mut binomial: i128 = 1;
+        let mut total: i128 = 0;
+        for (k, &c) in self.coefficients.iter().enumerate() {
+            if k > 0 {
+                // binomial(x, k) = binomial(x, k - 1) * (x - k + 1) / k exactly,
+                // even for negative x.
+                binomial = binomial.checked_mul(x - k as i128 + 1)? / k as i128;
+            }
+            total = total.checked_add((c as i128).checked_mul(binomial)?)?;
+        }
+        Some(total)
+    }
+
+    /// The values at `range`, in order, or `None` if any of them overflows.
+    pub fn values(&self, range: Range<i64>) -> Option<Vec<i128>> {
+        range.map(|x| self.at(x)).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::iter::zip;
+
+    use super::*;
+
+    fn matches(newton: &Newton, values: &[i64]) -> bool {
+        let found = newton.values(0..values.len() as i64).unwrap();
+        zip(found, values).all(|(a, &b)| a == b as i128)
+    }
+
+    #[test]
+    fn test_ratio() {
+        let half = Ratio::new(2, 4);
+        assert_eq!((half.numerator(), half.denominator()), (1, 2));
+        assert_eq!(Ratio::new(3, -6), -half);
+        assert_eq!(half + Ratio::new(1, 3), Ratio::new(5, 6));
+        assert_eq!(half - Ratio::from(1), Ratio::new(-1, 2));
+        assert_eq!(half * Ratio::from(4), Ratio::from(2));
+        assert_eq!(Ratio::from(1) / Ratio::new(-2, 3), Ratio::new(-3, 2));
+        assert_eq!(Ratio::new(-3, 2).to_string(), "-3/2");
+        assert_eq!(Ratio::from(0).to_integer(), Some(0));
+        assert_eq!(half.to_integer(), None);
+    }
+
+    #[test]
+    fn test_lagrange() {
+        let square = [(-1, 1), (2, 4), (5, 25)];
+        assert_eq!(lagrange(&square, 3), Ratio::from(9));
+        assert_eq!(lagrange(&square, -7), Ratio::from(49));
+        // A line through two points can land between integers.
+        assert_eq!(lagrange(&[(0, 0), (2, 1)], 1), Ratio::new(1, 2));
+        // Any one of these follows from the other three.
+        let far = [
+            (65, 3720),
+            (196, 33150),
+            (327, 91890),
+            (26501365, 599763113936220),
+        ];
+        for i in 0..far.len() {
+            let mut points = Vec::from(far);
+            let (x, y) = points.remove(i);
+            assert_eq!(lagrange(&points, x), y.into());
+        }
+    }
+
+    #[test]
+    fn test_differences() {
+        assert_eq!(
+            differences(&[1, 3, 6, 10, 15]),
+            [
+                vec![1, 3, 6, 10, 15],
+                vec![2, 3, 4, 5],
+                vec![1, 1, 1],
+                vec![0, 0]
+            ]
+        );
+        assert_eq!(degree(&[1, 3, 6, 10, 15]), Some(2));
+        assert_eq!(degree(&[7, 7]), Some(0));
+        assert_eq!(degree(&[0, 0, 0]), Some(0));
+        // Three values can't rule out a quadratic.
+        assert_eq!(degree(&[1, 2, 4]), None);
+        assert_eq!(degree(&[1, 2, 4, 8, 16, 32]), None);
+    }
+
+    #[test]
+    fn test_newton() {
+        let values = [10, 13, 16, 21, 30, 45];
+        let newton = Newton::new(&values);
+        assert!(matches(&newton, &values));
+        assert_eq!(newton.at(6), Some(68));
+        assert_eq!(newton.at(-1), Some(5));
+        assert_eq!(newton.values(-3..-1), Some(vec![-19, -4]));
+        // Beyond the values it agrees with Lagrange on any of them.
+        let points: Vec<_> = (0..).zip(values).collect();
+        for x in [-20, 9, 1000] {
+            assert_eq!(Ratio::new(newton.at(x).unwrap(), 1), lagrange(&points, x));
+        }
+        // Values that aren't polynomial still give the interpolating one.
+        let powers = [1, 2, 4, 8];
+        assert!(matches(&Newton::new(&powers), &powers));
+        assert_eq!(Newton::new(&powers).at(4), Some(15));
+
+        // The row of zeros isn't a coefficient, so far values that fit still
+        // come out, and ones that don't are caught.
+        let squares = Newton::new(&[0, 1, 4, 9]);
+        assert_eq!(squares.coefficients(), [0, 1, 2]);
+        let x = i64::MAX / 2;
+        assert_eq!(squares.at(x), Some(x as i128 * x as i128));
+        assert_eq!(Newton::new(&[0, 0, 0, 1]).at(i64::MAX), None);
+        assert_eq!(Newton::new(&[0, 0]).coefficients(), [0; 0]);
+    }
+}
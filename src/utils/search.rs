@@ -0,0 +1,236 @@
+//! Graph searches over any hashable state type, with the graph given by a
+//! successor closure.
+
+use std::{
+    cmp::Reverse,
+    collections::{hash_map::Entry, BinaryHeap, HashMap, VecDeque},
+    hash::Hash,
+    ops::Add,
+};
+
+/// The cheapest route found by a search, from a start state to a goal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Path<S, C> {
+    pub cost: C,
+    pub states: Vec<S>,
+}
+
+/// Every state seen so far, with the best known cost to reach it and the
+/// state it was reached from.
+struct Nodes<S, C> {
+    index: HashMap<S, usize>,
+    states: Vec<S>,
+    costs: Vec<C>,
+    parents: Vec<Option<usize>>,
+}
+
+impl<S: Clone + Eq + Hash, C: Copy> Nodes<S, C> {
+    fn new() -> Self {
+        Nodes {
+            index: HashMap::new(),
+            states: Vec::new(),
+            costs: Vec::new(),
+            parents: Vec::new(),
+        }
+    }
+
+    /// Records a route to `state`, returning its index if the route is new or
+    /// cheaper than the one already known.
+    fn visit(&mut self, state: S, cost: C, parent: Option<usize>) -> Option<usize>
+    where
+        C: Ord,
+    {
+        match self.index.entry(state) {
+            Entry::Occupied(entry) => {
+                let i = *entry.get();
+                if cost >= self.costs[i] {
+                    return None;
+                }
+                self.costs[i] = cost;
+                self.parents[i] = parent;
+                Some(i)
+            }
+            Entry::Vacant(entry) => {
+                let i = self.states.len();
+                self.states.push(entry.key().clone());
+                entry.insert(i);
+                self.costs.push(cost);
+                self.parents.push(parent);
+                Some(i)
+            }
+        }
+    }
+
+    fn path(&self, mut i: usize) -> Path<S, C> {
+        let cost = self.costs[i];
+        let mut states = vec![self.states[i].clone()];
+        while let Some(parent) = self.parents[i] {
+            states.push(self.states[parent].clone());
+            i = parent;
+        }
+        states.reverse();
+        Path { cost, states }
+    }
+}
+
+/// Finds the cheapest path from any of `starts` to a state satisfying
+/// `is_goal`. `heuristic` must never overestimate the remaining cost.
+pub fn astar<S, C, I>(
+    starts: impl IntoIterator<Item = S>,
+    mut successors: impl FnMut(&S) -> I,
+    mut heuristic: impl FnMut(&S) -> C,
+    mut is_goal: impl FnMut(&S) -> bool,
+) -> Option<Path<S, C>>
+where
+    S: Clone + Eq + Hash,
+    C: Copy + Ord + Add<Output = C> + Default,
+    I: IntoIterator<Item = (S, C)>,
+{
+    let mut nodes = Nodes::new();
+    let mut heap = BinaryHeap::new();
+    for start in starts {
+        let estimate = heuristic(&start);
+        if let Some(i) = nodes.visit(start, C::default(), None) {
+            heap.push(Reverse((estimate, C::default(), i)));
+        }
+    }
+    while let Some(Reverse((_, cost, i))) = heap.pop() {
+        if cost > nodes.costs[i] {
+            continue;
+        }
+        let state = nodes.states[i].clone();
+        if is_goal(&state) {
+            return Some(nodes.path(i));
+        }
+        for (next, step) in successors(&state) {
+            let cost = cost + step;
+            let estimate = cost + heuristic(&next);
+            if let Some(j) = nodes.visit(next, cost, Some(i)) {
+                heap.push(Reverse((estimate, cost, j)));
+            }
+        }
+    }
+    None
+}
+
+/// Finds the cheapest path from any of `starts` to a state satisfying
+/// `is_goal`.
+pub fn dijkstra<S, C, I>(
+    starts: impl IntoIterator<Item = S>,
+    successors: impl FnMut(&S) -> I,
+    is_goal: impl FnMut(&S) -> bool,
+) -> Option<Path<S, C>>
+where
+    S: Clone + Eq + Hash,
+    C: Copy + Ord + Add<Output = C> + Default,
+    I: IntoIterator<Item = (S, C)>,
+{
+    astar(starts, successors, |_| C::default(), is_goal)
+}
+
+/// Finds the path with the fewest steps from any of `starts` to a state
+/// satisfying `is_goal`.
+pub fn bfs<S, I>(
+    starts: impl IntoIterator<Item = S>,
+    mut successors: impl FnMut(&S) -> I,
+    mut is_goal: impl FnMut(&S) -> bool,
+) -> Option<Path<S, usize>>
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = S>,
+{
+    let mut nodes = Nodes::new();
+    let mut queue: VecDeque<_> = starts
+        .into_iter()
+        .filter_map(|start| nodes.visit(start, 0, None))
+        .collect();
+    while let Some(i) = queue.pop_front() {
+        let state = nodes.states[i].clone();
+        if is_goal(&state) {
+            return Some(nodes.path(i));
+        }
+        let cost = nodes.costs[i] + 1;
+        queue.extend(
+            successors(&state)
+                .into_iter()
+                .filter_map(|next| nodes.visit(next, cost, Some(i))),
+        );
+    }
+    None
+}
+
+/// The fewest steps needed to reach every state reachable from `starts`.
+pub fn distances<S, I>(
+    starts: impl IntoIterator<Item = S>,
+    mut successors: impl FnMut(&S) -> I,
+) -> HashMap<S, usize>
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = S>,
+{
+    let mut distances: HashMap<_, _> = starts.into_iter().map(|start| (start, 0)).collect();
+    let mut queue: VecDeque<_> = distances.keys().cloned().collect();
+    while let Some(state) = queue.pop_front() {
+        let distance = distances[&state] + 1;
+        for next in successors(&state) {
+            if let Entry::Vacant(entry) = distances.entry(next) {
+                queue.push_back(entry.key().clone());
+                entry.insert(distance);
+            }
+        }
+    }
+    distances
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // 0 -> 1 -> 3 is shorter in steps, 0 -> 2 -> 3 is cheaper.
+    fn edges(n: &u32) -> Vec<(u32, u32)> {
+        match n {
+            0 => vec![(1, 1), (2, 2)],
+            1 => vec![(3, 10)],
+            2 => vec![(3, 3)],
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn test_dijkstra() {
+        let path = dijkstra([0], edges, |&n| n == 3).unwrap();
+        assert_eq!(path.cost, 5);
+        assert_eq!(path.states, [0, 2, 3]);
+        assert_eq!(dijkstra([0], edges, |&n| n == 4), None);
+    }
+
+    #[test]
+    fn test_astar() {
+        // Manhattan distance on an open 10x10 grid.
+        let path = astar(
+            [(0, 0)],
+            |&(x, y): &(i32, i32)| {
+                [(x + 1, y), (x, y + 1), (x - 1, y), (x, y - 1)]
+                    .into_iter()
+                    .filter(|&(x, y)| (0..10).contains(&x) && (0..10).contains(&y))
+                    .map(|p| (p, 1))
+            },
+            |&(x, y)| (9 - x) + (9 - y),
+            |&p| p == (9, 9),
+        )
+        .unwrap();
+        assert_eq!(path.cost, 18);
+        assert_eq!(path.states.len(), 19);
+    }
+
+    #[test]
+    fn test_bfs() {
+        let successors = |n: &u32| edges(n).into_iter().map(|(m, _)| m);
+        let path = bfs([0], successors, |&n| n == 3).unwrap();
+        assert_eq!(path.cost, 2);
+        assert_eq!(path.states, [0, 1, 3]);
+        let distances = distances([0], successors);
+        assert_eq!(distances.len(), 4);
+        assert_eq!(distances[&3], 2);
+    }
+}